@@ -0,0 +1,464 @@
+use anyhow::{Result, anyhow};
+use gdal::raster::Buffer;
+use gdal::{Dataset, DriverManager};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Rows of pixels computed at a time by [`calculate_raster`]
+const CALCULATE_BLOCK_ROWS: usize = 256;
+
+/// Longest expression text accepted, in bytes
+const MAX_EXPRESSION_LENGTH: usize = 4096;
+
+/// Deepest nesting of parentheses, function calls, negations and powers accepted
+const MAX_NESTING_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    /// Band numbered from 1
+    Band(usize),
+    Negate(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Function(String, Vec<Expr>),
+}
+
+/// Arithmetic over raster bands, e.g. `(B4 - B3) / (B4 + B3)` for NDVI. Bands are written `B1`,
+/// `B2`, ...; numbers, `+ - * / ^`, parentheses and the functions `abs`, `sqrt`, `ln`, `log10`,
+/// `exp`, `min` and `max` are supported. Serialized as the expression text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BandExpression {
+    text: String,
+    expr: Expr,
+}
+
+impl BandExpression {
+    /// Bands the expression reads, ascending and without repeats
+    pub fn bands(&self) -> Vec<usize> {
+        fn collect(expr: &Expr, bands: &mut Vec<usize>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Band(band) => bands.push(*band),
+                Expr::Negate(inner) => collect(inner, bands),
+                Expr::Binary(_, left, right) => {
+                    collect(left, bands);
+                    collect(right, bands);
+                }
+                Expr::Function(_, args) => args.iter().for_each(|arg| collect(arg, bands)),
+            }
+        }
+        let mut bands = Vec::new();
+        collect(&self.expr, &mut bands);
+        bands.sort_unstable();
+        bands.dedup();
+        bands
+    }
+
+    /// Evaluate for one pixel, given the value of each band the expression reads. Results that
+    /// are not finite, such as a division by zero, are NaN.
+    pub fn evaluate(&self, band: impl Fn(usize) -> f64) -> f64 {
+        fn eval(expr: &Expr, band: &dyn Fn(usize) -> f64) -> f64 {
+            match expr {
+                Expr::Number(value) => *value,
+                Expr::Band(index) => band(*index),
+                Expr::Negate(inner) => -eval(inner, band),
+                Expr::Binary(op, left, right) => {
+                    let (left, right) = (eval(left, band), eval(right, band));
+                    match op {
+                        Operator::Add => left + right,
+                        Operator::Subtract => left - right,
+                        Operator::Multiply => left * right,
+                        Operator::Divide => left / right,
+                        Operator::Power => left.powf(right),
+                    }
+                }
+                Expr::Function(name, args) => {
+                    let args: Vec<f64> = args.iter().map(|arg| eval(arg, band)).collect();
+                    match name.as_str() {
+                        "abs" => args[0].abs(),
+                        "sqrt" => args[0].sqrt(),
+                        "ln" => args[0].ln(),
+                        "log10" => args[0].log10(),
+                        "exp" => args[0].exp(),
+                        "min" => args[0].min(args[1]),
+                        "max" => args[0].max(args[1]),
+                        _ => f64::NAN,
+                    }
+                }
+            }
+        }
+        let value = eval(&self.expr, &band);
+        if value.is_finite() { value } else { f64::NAN }
+    }
+}
+
+impl fmt::Display for BandExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl From<BandExpression> for String {
+    fn from(expression: BandExpression) -> Self {
+        expression.text
+    }
+}
+
+impl TryFrom<String> for BandExpression {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        if text.len() > MAX_EXPRESSION_LENGTH {
+            return Err(anyhow!(
+                "Band expression is longer than {} bytes",
+                MAX_EXPRESSION_LENGTH
+            ));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(&text)?,
+            position: 0,
+            depth: 0,
+        };
+        let expr = parser.expression()?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(anyhow!("Unexpected {:?} in band expression", token));
+        }
+        Ok(BandExpression { text, expr })
+    }
+}
+
+impl FromStr for BandExpression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        BandExpression::try_from(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Band(usize),
+    Name(String),
+    Symbol(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if "+-*/^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some((index, next)) = chars.peek().copied() {
+            if !(next.is_ascii_alphanumeric() || next == '.' || next == '_') {
+                break;
+            }
+            end = index + next.len_utf8();
+            chars.next();
+        }
+        let word = &text[start..end];
+        let token = if c.is_ascii_digit() || c == '.' {
+            Token::Number(
+                word.parse()
+                    .map_err(|_| anyhow!("Invalid number '{}' in band expression", word))?,
+            )
+        } else if let Some(band) = word
+            .strip_prefix(['B', 'b'])
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            if band == 0 {
+                return Err(anyhow!("Bands are numbered from 1, got '{}'", word));
+            }
+            Token::Band(band)
+        } else if c.is_ascii_alphabetic() {
+            Token::Name(word.to_ascii_lowercase())
+        } else {
+            return Err(anyhow!("Unexpected '{}' in band expression", word));
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser; `^` binds tightest and is right associative
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Levels of [`Parser::unary`] currently being parsed
+    depth: usize,
+}
+
+impl Parser {
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.tokens.get(self.position) == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(anyhow!(
+                "Band expression is nested more than {} levels deep",
+                MAX_NESTING_DEPTH
+            ));
+        }
+        let expr = self.unary_inner();
+        self.depth -= 1;
+        expr
+    }
+
+    fn unary_inner(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        let base = self.primary()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.advance() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Band(band)) => Ok(Expr::Band(band)),
+            Some(Token::Symbol('(')) => {
+                let expr = self.expression()?;
+                if !self.eat(')') {
+                    return Err(anyhow!("Missing ')' in band expression"));
+                }
+                Ok(expr)
+            }
+            Some(Token::Name(name)) => {
+                let arity = match name.as_str() {
+                    "abs" | "sqrt" | "ln" | "log10" | "exp" => 1,
+                    "min" | "max" => 2,
+                    _ => return Err(anyhow!("Unknown function '{}' in band expression", name)),
+                };
+                if !self.eat('(') {
+                    return Err(anyhow!("Expected '(' after '{}'", name));
+                }
+                let mut args = vec![self.expression()?];
+                while self.eat(',') {
+                    args.push(self.expression()?);
+                }
+                if !self.eat(')') {
+                    return Err(anyhow!("Missing ')' after arguments of '{}'", name));
+                }
+                if args.len() != arity {
+                    return Err(anyhow!(
+                        "'{}' takes {} argument(s), got {}",
+                        name,
+                        arity,
+                        args.len()
+                    ));
+                }
+                Ok(Expr::Function(name, args))
+            }
+            Some(token) => Err(anyhow!("Unexpected {:?} in band expression", token)),
+            None => Err(anyhow!("Band expression ends unexpectedly")),
+        }
+    }
+}
+
+/// Evaluate `expression` over a whole raster, producing a single band Float32 raster in memory
+/// with the source's georeference, e.g. to ingest a derived layer. Pixels where any band the
+/// expression reads is NoData, or where the result is not finite, are NaN, which is also the
+/// output's NoData value. Blocking.
+pub fn calculate_raster(dataset: &Dataset, expression: &BandExpression) -> Result<Dataset> {
+    let bands = expression.bands();
+    let no_data = bands
+        .iter()
+        .map(|index| {
+            dataset
+                .rasterband(*index)
+                .map(|band| band.no_data_value())
+                .map_err(|e| anyhow!("Raster has no band {}: {}", index, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (width, height) = dataset.raster_size();
+
+    let mem = DriverManager::get_driver_by_name("MEM")?;
+    let mut output = mem.create_with_band_type::<f32, _>("", width, height, 1)?;
+    if let Ok(geo_transform) = dataset.geo_transform() {
+        output.set_geo_transform(&geo_transform)?;
+    }
+    if let Ok(srs) = dataset.spatial_ref() {
+        output.set_spatial_ref(&srs)?;
+    }
+    let mut output_band = output.rasterband(1)?;
+    output_band.set_no_data_value(Some(f64::NAN))?;
+
+    for top in (0..height).step_by(CALCULATE_BLOCK_ROWS) {
+        let rows = CALCULATE_BLOCK_ROWS.min(height - top);
+        let inputs = bands
+            .iter()
+            .map(|index| {
+                let buffer = dataset.rasterband(*index)?.read_as::<f64>(
+                    (0, top as isize),
+                    (width, rows),
+                    (width, rows),
+                    None,
+                )?;
+                Ok(buffer.into_shape_and_vec().1)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let values = evaluate_pixels(expression, &bands, &no_data, &inputs, width * rows);
+        let mut buffer = Buffer::new(
+            (width, rows),
+            values.into_iter().map(|value| value as f32).collect(),
+        );
+        output_band.write((0, top as isize), (width, rows), &mut buffer)?;
+    }
+    Ok(output)
+}
+
+/// Evaluate `expression` for `count` pixels, where `inputs[i]` holds the values of `bands[i]`
+/// and `no_data[i]` its NoData value
+pub(crate) fn evaluate_pixels(
+    expression: &BandExpression,
+    bands: &[usize],
+    no_data: &[Option<f64>],
+    inputs: &[Vec<f64>],
+    count: usize,
+) -> Vec<f64> {
+    (0..count)
+        .map(|pixel| {
+            let missing = inputs.iter().zip(no_data).any(|(values, no_data)| {
+                values[pixel].is_nan() || no_data.is_some_and(|no_data| values[pixel] == no_data)
+            });
+            if missing {
+                return f64::NAN;
+            }
+            expression.evaluate(|band| {
+                bands
+                    .iter()
+                    .position(|index| *index == band)
+                    .map_or(f64::NAN, |position| inputs[position][pixel])
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str) -> f64 {
+        text.parse::<BandExpression>()
+            .unwrap()
+            .evaluate(|band| band as f64 * 10.0)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3"), 9.0);
+        assert_eq!(evaluate("2 * 3 ^ 2"), 18.0);
+        assert_eq!(evaluate("10 - 4 - 3"), 3.0);
+        assert_eq!(evaluate("-2 ^ 2"), -4.0);
+        assert_eq!(evaluate("(B2 - B1) / (B2 + B1)"), 10.0 / 30.0);
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(evaluate("(2 ^ 3) ^ 2"), 64.0);
+    }
+
+    #[test]
+    fn function_arity() {
+        assert_eq!(evaluate("max(B1, B3)"), 30.0);
+        assert!("min(B1)".parse::<BandExpression>().is_err());
+        assert!("abs(B1, B2)".parse::<BandExpression>().is_err());
+        assert!("foo(B1)".parse::<BandExpression>().is_err());
+    }
+
+    #[test]
+    fn nesting_depth_limit() {
+        let nested = |depth: usize| format!("{}B1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(
+            nested(MAX_NESTING_DEPTH - 1)
+                .parse::<BandExpression>()
+                .is_ok()
+        );
+        assert!(nested(MAX_NESTING_DEPTH).parse::<BandExpression>().is_err());
+        assert!("-".repeat(1000).parse::<BandExpression>().is_err());
+        assert!(
+            format!("{}1", "abs(".repeat(100))
+                .parse::<BandExpression>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn length_limit() {
+        let long = vec!["B1"; MAX_EXPRESSION_LENGTH / 2].join("+");
+        assert!(long.parse::<BandExpression>().is_err());
+    }
+
+    #[test]
+    fn bands_are_sorted_and_unique() {
+        let expression: BandExpression = "B3 + b1 * B3 - B2".parse().unwrap();
+        assert_eq!(expression.bands(), vec![1, 2, 3]);
+    }
+}
@@ -16,6 +16,20 @@ pub trait ConnectorBase: Send + Sync {
     // Create Layer in the data source
     async fn create_layer(&self, layer: &crate::file::LayerSchema) -> Result<()>;
 
+    /// Remove a layer and all of its data from the data source; missing layers are ignored
+    async fn drop_layer(&self, source: &LayerSource) -> Result<()>;
+
+    /// Rename a layer's backing storage, returning the source under its new name
+    async fn rename_layer(&self, source: &LayerSource, new_name: &str) -> Result<LayerSource>;
+
+    /// Copy a layer's data, indexes and constraints to `new_name` in the same namespace,
+    /// returning the source of the copy. Feature ids are preserved.
+    async fn copy_layer(&self, source: &LayerSource, new_name: &str) -> Result<LayerSource>;
+
+    /// Register a custom CRS with the data source, returning the SRID to store layers in. An
+    /// identical definition registered earlier is reused, so this is safe to call per ingest.
+    async fn register_crs(&self, crs: &crate::CrsDefinition) -> Result<crate::Srid>;
+
     /// List data sources, optionally filtered.
     async fn list_sources(&self) -> Result<Vec<String>>;
 
@@ -30,7 +44,8 @@ pub trait ConnectorBase: Send + Sync {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum LayerSource {
     Database {
         namespace: String,
@@ -42,6 +57,47 @@ pub enum LayerSource {
     // Uuid(Uuid),
 }
 
+/// Companion label layer emitted alongside the geometry layer of a vector tile
+#[derive(Debug, Clone, Default)]
+pub struct LabelOptions {
+    /// Name of the label layer inside the MVT, defaults to `{layer_name}_labels`
+    pub layer_name: Option<String>,
+    /// Attribute fields carried on each label point
+    pub fields: Vec<String>,
+}
+
+/// Options controlling what is emitted into a vector tile
+#[derive(Debug, Clone, Default)]
+pub struct TileOptions {
+    /// Emit polygon label points (ST_PointOnSurface) as a second MVT layer
+    pub labels: Option<LabelOptions>,
+    /// Tiling scheme used to interpret z/x/y
+    pub grid: crate::TileGrid,
+    /// Attribute filter applied to every feature in the tile
+    pub filter: Option<crate::Filter>,
+    /// Layer version mixed into the tile content hash
+    pub layer_version: Option<String>,
+}
+
+/// Options for [`VectorConnector::render_thumbnail`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailOptions {
+    /// Length of the longer image side in pixels; the other side follows the layer's aspect
+    /// ratio
+    pub size: u32,
+    /// RGB colour features are drawn in over a transparent background
+    pub color: [u8; 3],
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            size: 256,
+            color: [51, 136, 255],
+        }
+    }
+}
+
 /// Trait for all vector-based geospatial data sources
 #[async_trait]
 pub trait VectorConnector: ConnectorBase {
@@ -55,20 +111,251 @@ pub trait VectorConnector: ConnectorBase {
         x: u32,
         y: u32,
     ) -> Result<Vec<u8>>;
-    fn map_gdal_field_type(&self, field_type_str: &str) -> String;
+    async fn get_tile_with_options(
+        &self,
+        source: &LayerSource,
+        layer_name: &str,
+        z: u32,
+        x: u32,
+        y: u32,
+        options: &TileOptions,
+    ) -> Result<Vec<u8>>;
+
+    /// Fetch a tile along with a content hash for ETag/If-None-Match handling
+    async fn get_tile_hashed(
+        &self,
+        source: &LayerSource,
+        layer_name: &str,
+        z: u32,
+        x: u32,
+        y: u32,
+        options: &TileOptions,
+    ) -> Result<crate::Tile> {
+        let data = self
+            .get_tile_with_options(source, layer_name, z, x, y, options)
+            .await?;
+        Ok(crate::Tile::new(data, options.layer_version.as_deref()))
+    }
+
+    /// Build a UTFGrid interaction tile keyed on `key_field`, with z/x/y interpreted in `grid`
+    /// as for the [`TileOptions`] of the tiles it accompanies
+    async fn get_utfgrid(
+        &self,
+        source: &LayerSource,
+        z: u32,
+        x: u32,
+        y: u32,
+        key_field: &str,
+        grid: &crate::TileGrid,
+    ) -> Result<crate::UtfGrid>;
+
+    /// Features intersecting a bounding box as GeoJSON.
+    ///
+    /// Feature queries return GeoJSON geometry in EPSG:4326 unless `output` requests another CRS
+    /// or encoding, whatever the storage CRS of the layer.
+    async fn query_bbox(
+        &self,
+        source: &LayerSource,
+        bbox: &crate::Bbox,
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection>;
+
+    /// Features matching a spatial predicate against a caller-supplied geometry
+    async fn query_spatial(
+        &self,
+        source: &LayerSource,
+        geometry: &crate::QueryGeometry,
+        predicate: crate::SpatialPredicate,
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection>;
+
+    /// Attribute rows of matching features without geometry, for attribute tables and other
+    /// tabular views. Returns `fields` in order, or every non-geometry column when empty.
+    async fn query_attributes(
+        &self,
+        source: &LayerSource,
+        fields: &[String],
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+    ) -> Result<crate::AttributeTable>;
+
+    /// Single feature with geometry and all attributes
+    async fn get_feature(
+        &self,
+        source: &LayerSource,
+        id: i64,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::GeoJsonFeature>;
+
+    /// The `k` features nearest to a WGS84 point, optionally within `max_distance` metres.
+    /// Each feature carries a `distance` property in metres.
+    async fn nearest(
+        &self,
+        source: &LayerSource,
+        point: geo_types::Point<f64>,
+        k: u32,
+        max_distance: Option<f64>,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection>;
+
+    /// Min/max/mean/stddev, null count and an equal-width histogram of a numeric field
+    async fn field_stats(
+        &self,
+        source: &LayerSource,
+        field: &str,
+        bins: u32,
+    ) -> Result<crate::FieldStats>;
+
+    /// Most common values of a field with their counts, for filter dropdowns and categorical styles
+    async fn distinct_values(
+        &self,
+        source: &LayerSource,
+        field: &str,
+        limit: u32,
+    ) -> Result<Vec<crate::DistinctValue>>;
+
+    /// Coordinate dimensions of a layer's geometry column, as declared by its type. Columns
+    /// declared without dimensions are scanned for any Z or M coordinates.
+    async fn get_coordinate_dimension(&self, source: &LayerSource) -> Result<CoordinateDimension>;
+
+    /// Layer extent in EPSG:4326, or `None` for an empty layer. When `accurate` is false the
+    /// planner's estimate is used if available, falling back to a full scan.
+    async fn get_extent(&self, source: &LayerSource, accurate: bool)
+    -> Result<Option<crate::Bbox>>;
+
+    /// Number of features matching an optional filter
+    async fn count(&self, source: &LayerSource, filter: Option<&crate::Filter>) -> Result<u64>;
+
+    /// Feature counts per geometry type, most common first
+    async fn geometry_type_counts(
+        &self,
+        source: &LayerSource,
+    ) -> Result<Vec<crate::GeometryTypeCount>>;
+
+    /// Feature count, accurate extent, geometry type distribution and statistics for each of
+    /// the numeric `fields`, for storing with the layer after ingest
+    async fn compute_layer_stats(
+        &self,
+        source: &LayerSource,
+        fields: &[String],
+    ) -> Result<crate::LayerStats> {
+        let mut field_stats = Vec::with_capacity(fields.len());
+        for field in fields {
+            field_stats.push(
+                self.field_stats(source, field, crate::LAYER_STATS_HISTOGRAM_BINS)
+                    .await?,
+            );
+        }
+
+        Ok(crate::LayerStats {
+            feature_count: self.count(source, None).await?,
+            extent: self.get_extent(source, true).await?,
+            geometry_types: self.geometry_type_counts(source).await?,
+            fields: field_stats,
+            computed_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Stream a GeoJSON FeatureCollection of matching features, in EPSG:4326 unless another
+    /// `output_srid` is requested, suitable for download endpoints
+    fn export_geojson<'a>(
+        &'a self,
+        source: &'a LayerSource,
+        filter: Option<&'a crate::Filter>,
+        output_srid: Option<i32>,
+    ) -> futures::stream::BoxStream<'a, Result<bytes::Bytes>>;
+
+    /// Stream matching features as newline-delimited GeoJSON (one Feature per line), so very
+    /// large layers can be exported without buffering the whole dataset
+    fn export_ndjson<'a>(
+        &'a self,
+        source: &'a LayerSource,
+        filter: Option<&'a crate::Filter>,
+        output_srid: Option<i32>,
+    ) -> futures::stream::BoxStream<'a, Result<bytes::Bytes>>;
+
+    /// Stream `left` features enriched with `fields` from every `right` feature matching
+    /// `predicate` (e.g. points tagged with the polygon they fall in). A left feature is repeated
+    /// for each match and kept with null attributes when nothing matches.
+    fn spatial_join<'a>(
+        &'a self,
+        left: &'a LayerSource,
+        right: &'a LayerSource,
+        predicate: crate::SpatialPredicate,
+        fields: &'a [String],
+        output: crate::FeatureOutput,
+    ) -> futures::stream::BoxStream<'a, Result<crate::GeoJsonFeature>>;
+
+    /// Run the same join as [`VectorConnector::spatial_join`] inside the data source, writing
+    /// the result to a new layer `target_name` in the left layer's namespace. Joined field names
+    /// must not clash with columns of the left layer.
+    async fn materialize_spatial_join(
+        &self,
+        left: &LayerSource,
+        right: &LayerSource,
+        predicate: crate::SpatialPredicate,
+        fields: &[String],
+        target_name: &str,
+    ) -> Result<LayerSource>;
+
+    /// Render a PNG preview of the whole layer fitted to its extent in EPSG:3857, for layer
+    /// lists. Fails for a layer without geometries.
+    async fn render_thumbnail(
+        &self,
+        source: &LayerSource,
+        options: &ThumbnailOptions,
+    ) -> Result<Vec<u8>>;
+
+    /// Lowercase words the target reserves, which generated SQL must quote and which ingest
+    /// can rename columns away from
+    fn reserved_words(&self) -> &'static [&'static str];
+
+    /// Column type for a GDAL field type, bounded by the source `width` and `precision` where
+    /// given
+    fn map_gdal_field_type(
+        &self,
+        field_type_str: &str,
+        width: Option<i32>,
+        precision: Option<i32>,
+    ) -> String;
 }
 
 /// Trait for all raster-based geospatial data sources
 #[async_trait]
 pub trait RasterConnector: ConnectorBase {
     async fn get_raster_info(&self, source_id: &Uuid) -> Result<RasterInfo>;
-    async fn get_raster_tile(&self, source_id: &Uuid, z: u32, x: u32, y: u32) -> Result<Vec<u8>>;
+    async fn get_raster_tile(&self, source_id: &Uuid, z: u32, x: u32, y: u32) -> Result<Vec<u8>> {
+        self.get_raster_tile_with_options(source_id, z, x, y, &crate::RasterTileOptions::default())
+            .await
+    }
+
+    /// Render a tile with an explicit band mapping and per-band rescaling
+    async fn get_raster_tile_with_options(
+        &self,
+        source_id: &Uuid,
+        z: u32,
+        x: u32,
+        y: u32,
+        options: &crate::RasterTileOptions,
+    ) -> Result<Vec<u8>>;
 }
 
 /// Trait for connectors that support both vector and raster data
 #[async_trait]
 pub trait HybridConnector: VectorConnector + RasterConnector {}
 
+/// Serializable description of a connector, e.g. from a host application's JSON, YAML or TOML
+/// configuration, built with [`Connector::from_config`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectorConfig {
+    Postgis(crate::connector::postgis::PostgresConfig),
+}
+
 /// Main connector enum that wraps either vector or raster connectors
 pub enum Connector {
     Vector(Box<dyn VectorConnector>),
@@ -92,6 +379,27 @@ impl Connector {
         Connector::Hybrid(connector)
     }
 
+    /// Build the connector a configuration describes, connecting to its data source
+    pub async fn from_config(config: ConnectorConfig) -> Result<Self> {
+        match config {
+            ConnectorConfig::Postgis(config) => {
+                let connector = crate::connector::postgis::PostgisConnector::new(config).await?;
+                Ok(Connector::new_vector(Box::new(connector)))
+            }
+        }
+    }
+
+    /// Build the connector a configuration describes without contacting its data source, e.g.
+    /// when loading saved connections while a database is briefly unreachable
+    pub fn from_config_lazy(config: ConnectorConfig) -> Result<Self> {
+        match config {
+            ConnectorConfig::Postgis(config) => {
+                let connector = crate::connector::postgis::PostgisConnector::connect_lazy(config)?;
+                Ok(Connector::new_vector(Box::new(connector)))
+            }
+        }
+    }
+
     /// Get reference to vector connector if this is a vector connector
     pub fn as_vector(&self) -> Option<&dyn VectorConnector> {
         match self {
@@ -205,6 +513,89 @@ pub enum GeometryType {
     MultiLineString,
     MultiPolygon,
     GeometryCollection,
+    CircularString,
+    CompoundCurve,
+    CurvePolygon,
+    MultiCurve,
+    MultiSurface,
+}
+
+/// Coordinate dimensions of a geometry column: plain 2D, with elevation (Z), with a measure (M)
+/// or both
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CoordinateDimension {
+    #[default]
+    Xy,
+    Xyz,
+    Xym,
+    Xyzm,
+}
+
+impl CoordinateDimension {
+    pub fn new(has_z: bool, has_m: bool) -> Self {
+        match (has_z, has_m) {
+            (false, false) => CoordinateDimension::Xy,
+            (true, false) => CoordinateDimension::Xyz,
+            (false, true) => CoordinateDimension::Xym,
+            (true, true) => CoordinateDimension::Xyzm,
+        }
+    }
+
+    pub fn has_z(self) -> bool {
+        matches!(self, CoordinateDimension::Xyz | CoordinateDimension::Xyzm)
+    }
+
+    pub fn has_m(self) -> bool {
+        matches!(self, CoordinateDimension::Xym | CoordinateDimension::Xyzm)
+    }
+
+    /// Suffix appended to a PostGIS type modifier, e.g. `Z` in `geometry(PointZ, 4326)`
+    pub fn typmod_suffix(self) -> &'static str {
+        match self {
+            CoordinateDimension::Xy => "",
+            CoordinateDimension::Xyz => "Z",
+            CoordinateDimension::Xym => "M",
+            CoordinateDimension::Xyzm => "ZM",
+        }
+    }
+}
+
+/// How raster pixels are resampled when building overviews or rendering tiles. Categorical
+/// rasters such as land cover need `Nearest` or `Mode`, as interpolating class codes invents
+/// classes that do not exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resampling {
+    #[default]
+    Nearest,
+    Bilinear,
+    Cubic,
+    Average,
+    Mode,
+}
+
+impl Resampling {
+    /// Name GDAL uses for overview building and warping
+    pub fn gdal_name(self) -> &'static str {
+        match self {
+            Resampling::Nearest => "NEAREST",
+            Resampling::Bilinear => "BILINEAR",
+            Resampling::Cubic => "CUBIC",
+            Resampling::Average => "AVERAGE",
+            Resampling::Mode => "MODE",
+        }
+    }
+
+    /// Algorithm name for PostGIS raster functions, which lack `Average` and `Mode`
+    pub fn postgis_name(self) -> Option<&'static str> {
+        match self {
+            Resampling::Nearest => Some("NearestNeighbor"),
+            Resampling::Bilinear => Some("Bilinear"),
+            Resampling::Cubic => Some("Cubic"),
+            Resampling::Average | Resampling::Mode => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -214,4 +605,47 @@ pub struct RasterInfo {
     pub bands: u32,
     pub data_type: String,
     pub no_data_value: Option<f64>,
+    /// GDAL affine geotransform `[origin_x, pixel_width, row_rotation, origin_y,
+    /// column_rotation, pixel_height]`
+    #[serde(default)]
+    pub geo_transform: Option<[f64; 6]>,
+    /// `[min_x, min_y, max_x, max_y]` in the raster's CRS, `None` without a geotransform
+    #[serde(default)]
+    pub extent: Option<[f64; 4]>,
+    #[serde(default)]
+    pub srid: Option<i32>,
+    /// Source CRS when it has no EPSG code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crs: Option<crate::CrsDefinition>,
+    #[serde(default)]
+    pub band_info: Vec<RasterBandInfo>,
+    #[serde(default)]
+    pub overview_count: u32,
+    /// Decimation factor of each overview already present, e.g. `[2, 4, 8]`
+    #[serde(default)]
+    pub overview_levels: Vec<u32>,
+}
+
+/// Description of one raster band, numbered from 1 as in GDAL
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RasterBandInfo {
+    pub index: u32,
+    pub description: Option<String>,
+    pub data_type: String,
+    pub no_data_value: Option<f64>,
+    /// GDAL color interpretation, e.g. `Red` or `Gray`
+    pub color_interpretation: String,
+    pub unit: Option<String>,
+    /// Pixel statistics ignoring nodata, `None` when they could not be computed
+    #[serde(default)]
+    pub statistics: Option<BandStatistics>,
+}
+
+/// Summary statistics of a raster band's pixel values
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct BandStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub stddev: f64,
 }
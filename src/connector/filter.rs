@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+/// Literal value compared against an attribute in a [`Filter`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FilterValue {
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        FilterValue::Bool(value)
+    }
+}
+
+impl From<i64> for FilterValue {
+    fn from(value: i64) -> Self {
+        FilterValue::Integer(value)
+    }
+}
+
+impl From<f64> for FilterValue {
+    fn from(value: f64) -> Self {
+        FilterValue::Real(value)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        FilterValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        FilterValue::Text(value)
+    }
+}
+
+/// Attribute filter expression shared by feature queries and tile requests.
+///
+/// Connectors compile filters to parameterized SQL: field names are quoted as
+/// identifiers and values are always bound, never interpolated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Filter {
+    Eq {
+        field: String,
+        value: FilterValue,
+    },
+    Neq {
+        field: String,
+        value: FilterValue,
+    },
+    In {
+        field: String,
+        values: Vec<FilterValue>,
+    },
+    Like {
+        field: String,
+        pattern: String,
+    },
+    Between {
+        field: String,
+        low: FilterValue,
+        high: FilterValue,
+    },
+    And {
+        filters: Vec<Filter>,
+    },
+    Or {
+        filters: Vec<Filter>,
+    },
+}
+
+impl Filter {
+    pub fn eq(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Filter::Eq {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn neq(field: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Filter::Neq {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn is_in<V: Into<FilterValue>>(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Filter::In {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn like(field: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Filter::Like {
+            field: field.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn between(
+        field: impl Into<String>,
+        low: impl Into<FilterValue>,
+        high: impl Into<FilterValue>,
+    ) -> Self {
+        Filter::Between {
+            field: field.into(),
+            low: low.into(),
+            high: high.into(),
+        }
+    }
+
+    /// Combine this filter with another using AND
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Filter::And { mut filters } => {
+                filters.push(other);
+                Filter::And { filters }
+            }
+            filter => Filter::And {
+                filters: vec![filter, other],
+            },
+        }
+    }
+
+    /// Combine this filter with another using OR
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Filter::Or { mut filters } => {
+                filters.push(other);
+                Filter::Or { filters }
+            }
+            filter => Filter::Or {
+                filters: vec![filter, other],
+            },
+        }
+    }
+}
@@ -1,4 +1,19 @@
+mod band_math;
 mod core;
+mod filter;
 pub mod postgis;
+mod query;
+mod raster;
+mod serde_defaults;
+mod stats;
+mod terrain;
+mod tile;
 
+pub use band_math::*;
 pub use core::*;
+pub use filter::*;
+pub use query::*;
+pub use raster::*;
+pub use stats::*;
+pub use terrain::*;
+pub use tile::*;
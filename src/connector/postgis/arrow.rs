@@ -0,0 +1,204 @@
+use super::PostgisConnector;
+use super::postgis::{
+    EXPORT_BATCH_SIZE, ID_COLUMN, QuotedSource, push_filter_clause, push_page_clause,
+    quote_identifier, truncate_page,
+};
+use crate::{FeaturePage, Filter, LayerSource};
+use anyhow::{Result, anyhow};
+use arrow_array::{ArrayRef, BinaryArray, RecordBatch};
+use arrow_json::ReaderBuilder;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use futures::StreamExt;
+use futures::stream::{self, BoxStream};
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Arrow type for a Postgres column; anything without a direct equivalent is exported as text
+fn arrow_data_type(udt_name: &str) -> DataType {
+    match udt_name {
+        "bool" => DataType::Boolean,
+        "int2" => DataType::Int16,
+        "int4" => DataType::Int32,
+        "int8" => DataType::Int64,
+        "float4" => DataType::Float32,
+        "float8" | "numeric" => DataType::Float64,
+        "date" => DataType::Date32,
+        "timestamp" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        "timestamptz" => DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+        _ => DataType::Utf8,
+    }
+}
+
+/// WKB geometry field tagged with the GeoArrow extension type and CRS
+fn geometry_field(name: &str, srid: crate::Srid) -> Field {
+    let metadata = serde_json::json!({
+        "crs": format!("EPSG:{}", srid),
+        "crs_type": "authority_code",
+    });
+    Field::new(name, DataType::Binary, true).with_metadata(HashMap::from([
+        (
+            "ARROW:extension:name".to_string(),
+            "geoarrow.wkb".to_string(),
+        ),
+        ("ARROW:extension:metadata".to_string(), metadata.to_string()),
+    ]))
+}
+
+impl PostgisConnector {
+    /// Arrow schema of a layer: one field per attribute column followed by the geometry as a
+    /// GeoArrow WKB column, tagged with `output_srid` or the storage CRS when `None`. Only
+    /// available with the `arrow` feature.
+    pub async fn arrow_schema(
+        &self,
+        source: &LayerSource,
+        output_srid: Option<i32>,
+    ) -> Result<SchemaRef> {
+        let source = QuotedSource::new(source)?;
+        let srid = match output_srid {
+            Some(srid) => crate::Srid::try_from(srid)?,
+            None => source.srid,
+        };
+
+        let columns = sqlx::query_as::<_, (String, String)>(
+            "SELECT column_name::text, udt_name::text
+            FROM information_schema.columns
+            WHERE table_schema = $1 AND table_name = $2
+            ORDER BY ordinal_position",
+        )
+        .bind(source.namespace)
+        .bind(source.name)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to read columns of '{}': {}", source.name, e))?;
+
+        if columns.is_empty() {
+            return Err(anyhow!("Layer '{}' not found", source.name));
+        }
+
+        let mut fields: Vec<Field> = columns
+            .iter()
+            .filter(|(name, _)| name != source.geometry_field)
+            .map(|(name, udt_name)| Field::new(name, arrow_data_type(udt_name), true))
+            .collect();
+        fields.push(geometry_field(source.geometry_field, srid));
+
+        Ok(Arc::new(Schema::new(fields)))
+    }
+
+    /// Stream matching features as Arrow RecordBatches for hand-off to DataFusion, polars and
+    /// other Arrow-based tools. Geometry is WKB in the storage CRS unless another
+    /// `output_srid` is requested. Only available with the `arrow` feature.
+    pub fn export_arrow<'a>(
+        &'a self,
+        source: &'a LayerSource,
+        filter: Option<&'a Filter>,
+        output_srid: Option<i32>,
+    ) -> BoxStream<'a, Result<RecordBatch>> {
+        stream::once(self.arrow_schema(source, output_srid))
+            .flat_map(move |schema| match schema {
+                Ok(schema) => {
+                    stream::try_unfold(Some(FeaturePage::first(EXPORT_BATCH_SIZE)), move |page| {
+                        let schema = schema.clone();
+                        async move {
+                            let Some(page) = page else {
+                                return Ok::<_, anyhow::Error>(None);
+                            };
+                            let (batch, next_cursor) = self
+                                .arrow_batch(source, filter, output_srid, &schema, page)
+                                .await?;
+                            let next_page = next_cursor
+                                .map(|cursor| FeaturePage::after(EXPORT_BATCH_SIZE, cursor));
+                            Ok(Some((batch, next_page)))
+                        }
+                    })
+                    .boxed()
+                }
+                Err(e) => stream::once(async move { Err(e) }).boxed(),
+            })
+            .boxed()
+    }
+
+    /// One keyset page of features converted to a RecordBatch, with the cursor for the next
+    /// page
+    async fn arrow_batch(
+        &self,
+        source: &LayerSource,
+        filter: Option<&Filter>,
+        output_srid: Option<i32>,
+        schema: &SchemaRef,
+        page: FeaturePage,
+    ) -> Result<(RecordBatch, Option<i64>)> {
+        let source = QuotedSource::new(source)?;
+        let geometry = match output_srid {
+            Some(srid) => format!("ST_Transform(t.{}, {})", source.geometry, srid),
+            None => format!("t.{}", source.geometry),
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT to_jsonb(t) - ");
+        builder.push_bind(source.geometry_field.to_string());
+        builder.push(format!(
+            ", ST_AsBinary({geometry}), t.{id_col}::bigint FROM {schema}.{table} t WHERE TRUE",
+            geometry = geometry,
+            id_col = quote_identifier(ID_COLUMN)?,
+            schema = source.schema,
+            table = source.table
+        ));
+        push_filter_clause(&mut builder, filter)?;
+        push_page_clause(&mut builder, &page)?;
+
+        let mut rows = builder
+            .build_query_as::<(
+                Json<serde_json::Map<String, serde_json::Value>>,
+                Option<Vec<u8>>,
+                i64,
+            )>()
+            .fetch_all(&*self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to query features in '{}': {}", source.name, e))?;
+
+        let next_cursor = truncate_page(&mut rows, &page, |(_, _, id)| Some(*id));
+
+        // Attributes are decoded from JSON against every field but the trailing geometry;
+        // columns exported as text receive non-string JSON values in their serialized form
+        let attribute_fields = &schema.fields()[..schema.fields().len() - 1];
+        let attribute_schema = Arc::new(Schema::new(attribute_fields.to_vec()));
+        let mut properties = Vec::with_capacity(rows.len());
+        let mut geometries = Vec::with_capacity(rows.len());
+        for (row, geometry, _) in rows {
+            let mut row = row.0;
+            for field in attribute_fields
+                .iter()
+                .filter(|field| field.data_type() == &DataType::Utf8)
+            {
+                if let Some(value) = row
+                    .get_mut(field.name())
+                    .filter(|value| !value.is_string() && !value.is_null())
+                {
+                    *value = serde_json::Value::String(value.to_string());
+                }
+            }
+            properties.push(row);
+            geometries.push(geometry);
+        }
+
+        let mut decoder = ReaderBuilder::new(attribute_schema.clone())
+            .with_batch_size(properties.len().max(1))
+            .build_decoder()?;
+        decoder.serialize(&properties)?;
+        let attributes = decoder
+            .flush()?
+            .unwrap_or_else(|| RecordBatch::new_empty(attribute_schema));
+
+        let mut columns = attributes.columns().to_vec();
+        columns.push(Arc::new(BinaryArray::from(
+            geometries
+                .iter()
+                .map(|geometry| geometry.as_deref())
+                .collect::<Vec<_>>(),
+        )) as ArrayRef);
+
+        Ok((RecordBatch::try_new(schema.clone(), columns)?, next_cursor))
+    }
+}
@@ -1,3 +1,13 @@
+#[cfg(feature = "arrow")]
+mod arrow;
 mod postgis;
+mod raster;
+#[cfg(feature = "sql-query")]
+mod sql_query;
 
+#[cfg(feature = "arrow")]
+pub use arrow::*;
 pub use postgis::*;
+pub use raster::*;
+#[cfg(feature = "sql-query")]
+pub use sql_query::*;
@@ -0,0 +1,752 @@
+use super::PostgisConnector;
+use super::postgis::{ID_COLUMN, QuotedSource, quote_identifier};
+use crate::connector::serde_defaults::{default_band, default_true};
+use crate::{
+    BandStatistics, LayerSource, RasterBandInfo, RasterInfo, Resampling, Srid, VectorConnector,
+};
+use anyhow::{Result, anyhow};
+use gdal::Dataset;
+use gdal::raster::GdalDataType;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Column holding raster tiles in tables created by [`PostgisConnector::ingest_raster`]
+pub const RASTER_COLUMN: &str = "rast";
+
+/// Options for [`PostgisConnector::ingest_raster`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterIngestOptions {
+    /// Tile width and height in pixels; edge tiles are smaller
+    #[serde(default = "default_tile_size")]
+    pub tile_size: (u32, u32),
+    /// SRID to store the raster in, overriding the one the source declares. Must be left
+    /// unset or equal the target when `reproject` is set, as the warped raster is stored in the
+    /// target CRS.
+    #[serde(default)]
+    pub srid: Option<i32>,
+    /// Apply `AddRasterConstraints` so the table is registered in `raster_columns` with its
+    /// SRID, scale, block size and band types
+    #[serde(default = "default_true")]
+    pub add_constraints: bool,
+    /// Decimation factors of in-database overviews to build, e.g. `[2, 4, 8]`
+    #[serde(default)]
+    pub overview_factors: Vec<u32>,
+    #[serde(default)]
+    pub overview_resampling: Resampling,
+    /// Warp the raster into this CRS before it is tiled, e.g. to serve it straight from
+    /// PostGIS in the tile grid's CRS
+    #[serde(default)]
+    pub reproject: Option<Srid>,
+    /// Resampling used when reprojecting
+    #[serde(default)]
+    pub reproject_resampling: Resampling,
+}
+
+fn default_tile_size() -> (u32, u32) {
+    (256, 256)
+}
+
+impl Default for RasterIngestOptions {
+    fn default() -> Self {
+        RasterIngestOptions {
+            tile_size: default_tile_size(),
+            srid: None,
+            add_constraints: true,
+            overview_factors: Vec::new(),
+            overview_resampling: Resampling::default(),
+            reproject: None,
+            reproject_resampling: Resampling::default(),
+        }
+    }
+}
+
+/// Result of [`PostgisConnector::ingest_raster`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterIngestSummary {
+    pub table_name: String,
+    pub tile_count: u64,
+    pub srid: i32,
+    /// Overview tables created, named `o_<factor>_<table>` by PostGIS
+    pub overview_tables: Vec<String>,
+}
+
+/// Value of one band at a point, from [`PostgisConnector::sample_raster`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterSample {
+    pub band: u32,
+    /// `None` where the pixel is NoData or the point lies outside the raster
+    pub value: Option<f64>,
+}
+
+/// Where [`PostgisConnector::zonal_statistics`] writes its results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ZonalStatsOutput {
+    /// Add the statistics columns to the vector layer itself
+    Columns,
+    /// Copy the vector layer to a new layer `name` and add the columns there
+    NewLayer { name: String },
+}
+
+/// Options for [`PostgisConnector::zonal_statistics`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZonalStatsOptions {
+    /// Raster band to summarise, numbered from 1
+    #[serde(default = "default_band")]
+    pub band: u32,
+    /// Prefix of the `count`, `min`, `max` and `mean` columns
+    #[serde(default = "default_zonal_prefix")]
+    pub prefix: String,
+    pub output: ZonalStatsOutput,
+}
+
+fn default_zonal_prefix() -> String {
+    "zonal_".to_string()
+}
+
+/// PostGIS raster pixel type code for a GDAL band type
+fn pixel_type(data_type: GdalDataType) -> Result<u8> {
+    Ok(match data_type {
+        GdalDataType::Int8 => 3,
+        GdalDataType::UInt8 => 4,
+        GdalDataType::Int16 => 5,
+        GdalDataType::UInt16 => 6,
+        GdalDataType::Int32 => 7,
+        GdalDataType::UInt32 => 8,
+        GdalDataType::Float32 => 10,
+        GdalDataType::Float64 => 11,
+        other => return Err(anyhow!("Unsupported raster band type {:?}", other)),
+    })
+}
+
+/// Append one value in a PostGIS pixel type, little-endian
+fn push_pixel(wkb: &mut Vec<u8>, pixel_type: u8, value: f64) {
+    match pixel_type {
+        3 => wkb.extend_from_slice(&(value as i8).to_le_bytes()),
+        4 => wkb.extend_from_slice(&(value as u8).to_le_bytes()),
+        5 => wkb.extend_from_slice(&(value as i16).to_le_bytes()),
+        6 => wkb.extend_from_slice(&(value as u16).to_le_bytes()),
+        7 => wkb.extend_from_slice(&(value as i32).to_le_bytes()),
+        8 => wkb.extend_from_slice(&(value as u32).to_le_bytes()),
+        10 => wkb.extend_from_slice(&(value as f32).to_le_bytes()),
+        _ => wkb.extend_from_slice(&value.to_le_bytes()),
+    }
+}
+
+/// Raster read by [`PostgisConnector::ingest_raster`]: the source, or a warped view of it that
+/// reads from the source on demand. `warped` is declared first so it is dropped first.
+struct IngestSource {
+    warped: Option<Dataset>,
+    source: Dataset,
+}
+
+impl IngestSource {
+    fn dataset(&self) -> &Dataset {
+        self.warped.as_ref().unwrap_or(&self.source)
+    }
+}
+
+/// Encode one tile of `dataset` in the PostGIS raster WKB format read by `ST_RastFromWKB`
+fn tile_wkb(
+    dataset: &Dataset,
+    geo_transform: &[f64; 6],
+    srid: i32,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+) -> Result<Vec<u8>> {
+    let band_count = dataset.raster_count();
+    let mut wkb = Vec::with_capacity(61 + width * height * band_count);
+    // Little-endian, format version 0
+    wkb.push(1);
+    wkb.extend_from_slice(&0u16.to_le_bytes());
+    wkb.extend_from_slice(&(band_count as u16).to_le_bytes());
+    // Scale, upper-left corner of this tile and skew
+    let (px, py) = (x as f64, y as f64);
+    for value in [
+        geo_transform[1],
+        geo_transform[5],
+        geo_transform[0] + px * geo_transform[1] + py * geo_transform[2],
+        geo_transform[3] + px * geo_transform[4] + py * geo_transform[5],
+        geo_transform[2],
+        geo_transform[4],
+    ] {
+        wkb.extend_from_slice(&value.to_le_bytes());
+    }
+    wkb.extend_from_slice(&srid.to_le_bytes());
+    wkb.extend_from_slice(&(width as u16).to_le_bytes());
+    wkb.extend_from_slice(&(height as u16).to_le_bytes());
+
+    for band in dataset.rasterbands() {
+        let band = band?;
+        let pixel_type = pixel_type(band.band_type())?;
+        let no_data = band.no_data_value();
+        // Flags in the high bits: 0x40 marks a band with a nodata value
+        wkb.push(pixel_type | if no_data.is_some() { 0x40 } else { 0 });
+        push_pixel(&mut wkb, pixel_type, no_data.unwrap_or(0.0));
+
+        let buffer = band.read_as::<f64>(
+            (x as isize, y as isize),
+            (width, height),
+            (width, height),
+            None,
+        )?;
+        for value in buffer.data() {
+            push_pixel(&mut wkb, pixel_type, *value);
+        }
+    }
+    Ok(wkb)
+}
+
+/// Create in-database overview tables of a raster table. `ST_CreateOverview` also registers
+/// each table in `raster_overviews`, where clients find it.
+async fn create_overviews(
+    connection: &mut sqlx::PgConnection,
+    schema: &str,
+    table_name: &str,
+    factors: &[u32],
+    resampling: Resampling,
+) -> Result<Vec<String>> {
+    if factors.is_empty() {
+        return Ok(Vec::new());
+    }
+    let algorithm = resampling.postgis_name().ok_or_else(|| {
+        anyhow!(
+            "{:?} resampling is not available for in-database overviews",
+            resampling
+        )
+    })?;
+    let table = format!(
+        "{}.{}",
+        quote_identifier(schema)?,
+        quote_identifier(table_name)?
+    );
+
+    let mut overview_tables = Vec::new();
+    for factor in factors {
+        let (overview,): (String,) =
+            sqlx::query_as("SELECT ST_CreateOverview($1::regclass, $2::name, $3, $4)::text")
+                .bind(&table)
+                .bind(RASTER_COLUMN)
+                .bind(*factor as i32)
+                .bind(algorithm)
+                .fetch_one(&mut *connection)
+                .await
+                .map_err(|e| anyhow!("Failed to create overview {}: {}", factor, e))?;
+        overview_tables.push(overview);
+    }
+    Ok(overview_tables)
+}
+
+/// GDAL type name for a PostGIS pixel type, e.g. `Byte` for `8BUI`
+fn gdal_type_name(pixel_type: &str) -> &'static str {
+    match pixel_type {
+        "8BSI" => "Int8",
+        "8BUI" => "Byte",
+        "16BSI" => "Int16",
+        "16BUI" => "UInt16",
+        "32BSI" => "Int32",
+        "32BUI" => "UInt32",
+        "32BF" => "Float32",
+        "64BF" => "Float64",
+        _ => "Unknown",
+    }
+}
+
+impl PostgisConnector {
+    /// Build in-database overviews of a raster table loaded by
+    /// [`ingest_raster`](Self::ingest_raster), so low-zoom tiles read a few coarse tiles
+    /// instead of the whole raster. Returns the overview table names.
+    pub async fn build_raster_overviews(
+        &self,
+        table_name: &str,
+        factors: &[u32],
+        resampling: Resampling,
+    ) -> Result<Vec<String>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+        let overview_tables =
+            create_overviews(&mut tx, &self.schema, table_name, factors, resampling).await?;
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit overviews of '{}': {}", table_name, e))?;
+        debug!(
+            "Built {} overviews of raster '{}'",
+            overview_tables.len(),
+            table_name
+        );
+        Ok(overview_tables)
+    }
+
+    /// Load a raster into a new table of `raster` tiles, the equivalent of `raster2pgsql -I -C`.
+    /// The source is cut into `tile_size` blocks, each stored as a row with its own
+    /// georeference; a spatial index is built on the tile footprints, constraints are added and
+    /// overviews are created as requested. Requires the `postgis_raster` extension.
+    pub async fn ingest_raster(
+        &self,
+        dataset: Dataset,
+        table_name: &str,
+        options: &RasterIngestOptions,
+    ) -> Result<RasterIngestSummary> {
+        let schema = quote_identifier(&self.schema)?;
+        let table = quote_identifier(table_name)?;
+        let column = quote_identifier(RASTER_COLUMN)?;
+        let (tile_width, tile_height) = options.tile_size;
+        if tile_width == 0 || tile_height == 0 || tile_width > 65535 || tile_height > 65535 {
+            return Err(anyhow!("Invalid raster tile size {:?}", options.tile_size));
+        }
+
+        if let (Some(srid), Some(target)) = (options.srid, options.reproject)
+            && srid != i32::from(target)
+        {
+            return Err(anyhow!(
+                "SRID {} conflicts with reprojecting to EPSG:{}",
+                srid,
+                target
+            ));
+        }
+
+        let reproject = options.reproject;
+        let reproject_resampling = options.reproject_resampling;
+        let (source, srid, geo_transform, (width, height)) =
+            tokio::task::spawn_blocking(move || -> Result<_> {
+                // Tiles are read through the warped VRT, so the raster is never materialized
+                let source = IngestSource {
+                    warped: reproject
+                        .map(|target| {
+                            crate::connector::raster::warped_vrt(
+                                &dataset,
+                                target,
+                                reproject_resampling,
+                            )
+                        })
+                        .transpose()?,
+                    source: dataset,
+                };
+                let dataset = source.dataset();
+                if dataset.raster_count() == 0 {
+                    return Err(anyhow!("Dataset has no raster bands"));
+                }
+                for band in dataset.rasterbands() {
+                    pixel_type(band?.band_type())?;
+                }
+                let geo_transform = dataset
+                    .geo_transform()
+                    .map_err(|e| anyhow!("Raster has no geotransform: {}", e))?;
+                let srid = dataset
+                    .spatial_ref()
+                    .ok()
+                    .and_then(|srs| srs.auth_code().ok());
+                let size = dataset.raster_size();
+                Ok((source, srid, geo_transform, size))
+            })
+            .await
+            .map_err(|e| anyhow!("Raster task failed: {}", e))??;
+        let srid = match reproject {
+            Some(target) => i32::from(target),
+            None => options.srid.or(srid).unwrap_or(0),
+        };
+
+        debug!(
+            "Ingesting {}x{} raster into '{}'",
+            width, height, table_name
+        );
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        sqlx::query(&format!(
+            "CREATE TABLE {}.{} (rid SERIAL PRIMARY KEY, {} raster NOT NULL)",
+            schema, table, column
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to create raster table '{}': {}", table_name, e))?;
+
+        let insert_sql = format!(
+            "INSERT INTO {}.{} ({}) VALUES (ST_RastFromWKB($1))",
+            schema, table, column
+        );
+        let (tile_width, tile_height) = (tile_width as usize, tile_height as usize);
+        let mut source = source;
+        let mut tile_count = 0u64;
+        // One row of tiles is read at a time, keeping memory bounded for large rasters
+        for y in (0..height).step_by(tile_height) {
+            let (returned, tiles) = tokio::task::spawn_blocking(move || {
+                let tiles = (0..width)
+                    .step_by(tile_width)
+                    .map(|x| {
+                        let size = (tile_width.min(width - x), tile_height.min(height - y));
+                        tile_wkb(source.dataset(), &geo_transform, srid, (x, y), size)
+                    })
+                    .collect::<Result<Vec<_>>>();
+                (source, tiles)
+            })
+            .await
+            .map_err(|e| anyhow!("Raster task failed: {}", e))?;
+            source = returned;
+
+            for tile in tiles? {
+                sqlx::query(&insert_sql)
+                    .bind(tile)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| anyhow!("Failed to insert raster tile: {}", e))?;
+                tile_count += 1;
+            }
+        }
+
+        sqlx::query(&format!(
+            "CREATE INDEX ON {}.{} USING gist (ST_ConvexHull({}))",
+            schema, table, column
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to index raster table '{}': {}", table_name, e))?;
+
+        if options.add_constraints {
+            sqlx::query("SELECT AddRasterConstraints($1::name, $2::name, $3::name)")
+                .bind(&self.schema)
+                .bind(table_name)
+                .bind(RASTER_COLUMN)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| anyhow!("Failed to add raster constraints: {}", e))?;
+        }
+
+        let overview_tables = create_overviews(
+            &mut tx,
+            &self.schema,
+            table_name,
+            &options.overview_factors,
+            options.overview_resampling,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit raster '{}': {}", table_name, e))?;
+
+        debug!("Ingested {} raster tiles into '{}'", tile_count, table_name);
+        Ok(RasterIngestSummary {
+            table_name: table_name.to_string(),
+            tile_count,
+            srid,
+            overview_tables,
+        })
+    }
+
+    /// Describe a raster table loaded by [`ingest_raster`](Self::ingest_raster) with the same
+    /// [`RasterInfo`] as [`crate::file::extract_raster_info`] gives for files. Band statistics
+    /// are exact and scan every tile.
+    pub async fn raster_info(&self, table_name: &str) -> Result<RasterInfo> {
+        let schema = quote_identifier(&self.schema)?;
+        let table = quote_identifier(table_name)?;
+        let column = quote_identifier(RASTER_COLUMN)?;
+
+        let (srid, band_count, scale_x, scale_y, skew_x, skew_y): (i32, i32, f64, f64, f64, f64) =
+            sqlx::query_as(&format!(
+                "SELECT ST_SRID({c}), ST_NumBands({c}), ST_ScaleX({c}), ST_ScaleY({c}),
+                    ST_SkewX({c}), ST_SkewY({c})
+                FROM {}.{} LIMIT 1",
+                schema,
+                table,
+                c = column
+            ))
+            .fetch_optional(&*self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to read raster '{}': {}", table_name, e))?
+            .ok_or_else(|| anyhow!("Raster '{}' has no tiles", table_name))?;
+
+        let (min_x, min_y, max_x, max_y): (f64, f64, f64, f64) = sqlx::query_as(&format!(
+            "SELECT ST_XMin(e), ST_YMin(e), ST_XMax(e), ST_YMax(e)
+            FROM (SELECT ST_Extent(ST_Envelope({})) AS e FROM {}.{}) extent",
+            column, schema, table
+        ))
+        .fetch_one(&*self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to read extent of raster '{}': {}", table_name, e))?;
+
+        let mut band_info = Vec::with_capacity(band_count as usize);
+        for band in 1..=band_count {
+            let (pixel_type, no_data_value, min, max, mean, stddev): (
+                String,
+                Option<f64>,
+                Option<f64>,
+                Option<f64>,
+                Option<f64>,
+                Option<f64>,
+            ) = sqlx::query_as(&format!(
+                "SELECT
+                    (SELECT ST_BandPixelType({c}, $1) FROM {t} LIMIT 1),
+                    (SELECT ST_BandNoDataValue({c}, $1) FROM {t} LIMIT 1),
+                    s.min, s.max, s.mean, s.stddev
+                FROM (SELECT (ST_SummaryStatsAgg({c}, $1, true)).* FROM {t}) s",
+                c = column,
+                t = format!("{}.{}", schema, table)
+            ))
+            .bind(band)
+            .fetch_one(&*self.pool)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to read band {} of raster '{}': {}",
+                    band,
+                    table_name,
+                    e
+                )
+            })?;
+
+            band_info.push(RasterBandInfo {
+                index: band as u32,
+                description: None,
+                data_type: gdal_type_name(&pixel_type).to_string(),
+                no_data_value,
+                // PostGIS stores no color interpretation
+                color_interpretation: "Undefined".to_string(),
+                unit: None,
+                statistics: match (min, max, mean, stddev) {
+                    (Some(min), Some(max), Some(mean), Some(stddev)) => Some(BandStatistics {
+                        min,
+                        max,
+                        mean,
+                        stddev,
+                    }),
+                    _ => None,
+                },
+            });
+        }
+
+        let overview_levels: Vec<(i32,)> = sqlx::query_as(
+            "SELECT overview_factor FROM raster_overviews
+            WHERE r_table_schema = $1 AND r_table_name = $2
+            ORDER BY overview_factor",
+        )
+        .bind(&self.schema)
+        .bind(table_name)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to read overviews of raster '{}': {}", table_name, e))?;
+        let overview_levels: Vec<u32> = overview_levels
+            .into_iter()
+            .map(|(factor,)| factor as u32)
+            .collect();
+
+        let first_band = band_info
+            .first()
+            .ok_or_else(|| anyhow!("Raster '{}' has no bands", table_name))?;
+        Ok(RasterInfo {
+            width: ((max_x - min_x) / scale_x.abs()).round() as u32,
+            height: ((max_y - min_y) / scale_y.abs()).round() as u32,
+            bands: band_count as u32,
+            data_type: first_band.data_type.clone(),
+            no_data_value: first_band.no_data_value,
+            geo_transform: Some([min_x, scale_x, skew_x, max_y, skew_y, scale_y]),
+            extent: Some([min_x, min_y, max_x, max_y]),
+            srid: (srid > 0).then_some(srid),
+            crs: None,
+            band_info,
+            overview_count: overview_levels.len() as u32,
+            overview_levels,
+        })
+    }
+
+    /// Pixel values at a WGS 84 longitude/latitude, e.g. for "click for elevation" lookups.
+    /// `bands` are numbered from 1; empty samples every band.
+    pub async fn sample_raster(
+        &self,
+        table_name: &str,
+        lon: f64,
+        lat: f64,
+        bands: &[u32],
+    ) -> Result<Vec<RasterSample>> {
+        let schema = quote_identifier(&self.schema)?;
+        let table = quote_identifier(table_name)?;
+        let column = quote_identifier(RASTER_COLUMN)?;
+        let source = format!("{}.{}", schema, table);
+
+        let bands: Vec<i32> = if bands.is_empty() {
+            let (band_count,): (i32,) = sqlx::query_as(&format!(
+                "SELECT ST_NumBands({}) FROM {} LIMIT 1",
+                column, source
+            ))
+            .fetch_optional(&*self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to read raster '{}': {}", table_name, e))?
+            .ok_or_else(|| anyhow!("Raster '{}' has no tiles", table_name))?;
+            (1..=band_count).collect()
+        } else {
+            bands.iter().map(|band| *band as i32).collect()
+        };
+
+        let rows: Vec<(i32, Option<f64>)> = sqlx::query_as(&format!(
+            "SELECT b.band, ST_Value(tile.{c}, b.band, p.geom, true)::float8
+            FROM (
+                SELECT ST_Transform(ST_SetSRID(ST_MakePoint($1, $2), 4326), ST_SRID({c})) AS geom
+                FROM {t} LIMIT 1
+            ) p
+            CROSS JOIN unnest($3::int[]) WITH ORDINALITY AS b(band, position)
+            LEFT JOIN LATERAL (
+                SELECT {c} FROM {t} WHERE ST_Intersects({c}, p.geom) LIMIT 1
+            ) tile ON true
+            ORDER BY b.position",
+            c = column,
+            t = source
+        ))
+        .bind(lon)
+        .bind(lat)
+        .bind(&bands)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to sample raster '{}': {}", table_name, e))?;
+        if rows.is_empty() {
+            return Err(anyhow!("Raster '{}' has no tiles", table_name));
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|(band, value)| RasterSample {
+                band: band as u32,
+                value,
+            })
+            .collect())
+    }
+
+    /// Summarise a raster band under each polygon of a vector layer, storing the pixel count
+    /// and the min, max and mean value as `<prefix>count` etc. columns. NoData pixels are
+    /// ignored; polygons that cover no pixels get a count of 0 and NULL statistics. Returns the
+    /// layer holding the results.
+    pub async fn zonal_statistics(
+        &self,
+        raster_table: &str,
+        vector: &LayerSource,
+        options: &ZonalStatsOptions,
+    ) -> Result<LayerSource> {
+        let raster = format!(
+            "{}.{}",
+            quote_identifier(&self.schema)?,
+            quote_identifier(raster_table)?
+        );
+        let column = quote_identifier(RASTER_COLUMN)?;
+        let (raster_srid,): (i32,) = sqlx::query_as(&format!(
+            "SELECT ST_SRID({}) FROM {} LIMIT 1",
+            column, raster
+        ))
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to read raster '{}': {}", raster_table, e))?
+        .ok_or_else(|| anyhow!("Raster '{}' has no tiles", raster_table))?;
+
+        let target = match &options.output {
+            ZonalStatsOutput::Columns => vector.clone(),
+            ZonalStatsOutput::NewLayer { name } => self.copy_layer(vector, name).await?,
+        };
+        let result = self
+            .write_zonal_statistics(&raster, raster_srid, &target, options)
+            .await;
+        if result.is_err()
+            && let ZonalStatsOutput::NewLayer { .. } = options.output
+        {
+            let quoted = QuotedSource::new(&target)?;
+            let drop = format!("DROP TABLE IF EXISTS {}.{}", quoted.schema, quoted.table);
+            if let Err(e) = sqlx::query(&drop).execute(&*self.pool).await {
+                tracing::warn!("Failed to remove layer '{}': {}", quoted.name, e);
+            }
+        }
+        result.map(|_| target)
+    }
+
+    async fn write_zonal_statistics(
+        &self,
+        raster: &str,
+        raster_srid: i32,
+        target: &LayerSource,
+        options: &ZonalStatsOptions,
+    ) -> Result<()> {
+        let quoted = QuotedSource::new(target)?;
+        let column = quote_identifier(RASTER_COLUMN)?;
+        let id_column = quote_identifier(ID_COLUMN)?;
+        let stat_column = |stat: &str| quote_identifier(&format!("{}{}", options.prefix, stat));
+        let (count, min, max, mean) = (
+            stat_column("count")?,
+            stat_column("min")?,
+            stat_column("max")?,
+            stat_column("mean")?,
+        );
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let alter = format!(
+            "ALTER TABLE {}.{}
+                ADD COLUMN IF NOT EXISTS {} bigint,
+                ADD COLUMN IF NOT EXISTS {} double precision,
+                ADD COLUMN IF NOT EXISTS {} double precision,
+                ADD COLUMN IF NOT EXISTS {} double precision",
+            quoted.schema, quoted.table, count, min, max, mean
+        );
+        debug!("Executing SQL: {}", alter);
+        sqlx::query(&alter)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to add columns to '{}': {}", quoted.name, e))?;
+
+        // The polygon is moved into the raster's CRS so the index on the tiles can be used
+        let update = format!(
+            "UPDATE {schema}.{table} v
+            SET {count} = COALESCE((s.stats).count, 0), {min} = (s.stats).min,
+                {max} = (s.stats).max, {mean} = (s.stats).mean
+            FROM {schema}.{table} f
+            CROSS JOIN LATERAL (
+                SELECT ST_SummaryStatsAgg(ST_Clip(r.{rast}, $1, g.geom, true), 1, true) AS stats
+                FROM {raster} r, (SELECT ST_Transform(f.{geom}, $2) AS geom) g
+                WHERE ST_Intersects(r.{rast}, g.geom)
+            ) s
+            WHERE v.{id} = f.{id}",
+            schema = quoted.schema,
+            table = quoted.table,
+            count = count,
+            min = min,
+            max = max,
+            mean = mean,
+            rast = column,
+            raster = raster,
+            geom = quoted.geometry,
+            id = id_column
+        );
+        debug!("Executing SQL: {}", update);
+        let updated = sqlx::query(&update)
+            .bind(options.band as i32)
+            .bind(raster_srid)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to compute zonal statistics for '{}': {}",
+                    quoted.name,
+                    e
+                )
+            })?
+            .rows_affected();
+
+        tx.commit().await.map_err(|e| {
+            anyhow!(
+                "Failed to commit zonal statistics for '{}': {}",
+                quoted.name,
+                e
+            )
+        })?;
+        debug!(
+            "Computed zonal statistics for {} features of '{}'",
+            updated, quoted.name
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use super::PostgresConfig;
+use super::postgis::quote_identifier;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::types::Json;
+use sqlx::{Column, Executor, Statement, TypeInfo};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Limits applied to ad-hoc SQL queries
+#[derive(Debug, Clone)]
+pub struct SqlQueryOptions {
+    /// Statement timeout enforced by the database
+    pub statement_timeout: Duration,
+    /// Maximum number of rows returned; extra rows set `truncated`
+    pub max_rows: u32,
+}
+
+impl Default for SqlQueryOptions {
+    fn default() -> Self {
+        SqlQueryOptions {
+            statement_timeout: Duration::from_secs(10),
+            max_rows: 1000,
+        }
+    }
+}
+
+/// Result column name and Postgres type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlColumn {
+    pub name: String,
+    pub type_name: String,
+}
+
+/// Rows returned by an ad-hoc SQL query, with values in column order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlQueryResult {
+    pub columns: Vec<SqlColumn>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// True when more than `max_rows` rows matched
+    pub truncated: bool,
+}
+
+/// Whether `sql` contains a `;` outside string literals, quoted identifiers, dollar-quoted
+/// strings and comments
+fn has_statement_separator(sql: &str) -> bool {
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' => return true,
+            b'\'' => {
+                // E'...' strings also escape quotes with a backslash
+                let escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !(bytes[i - 2].is_ascii_alphanumeric() || bytes[i - 2] == b'_'));
+                i += 1;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'\\' if escapes => i += 1,
+                        b'\'' if bytes.get(i + 1) == Some(&b'\'') => i += 1,
+                        b'\'' => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            b'"' => {
+                // A doubled quote inside an identifier reopens it on the next iteration
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                // Block comments nest in Postgres
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 1;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            b'$' if i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_') => {
+                // $tag$ ... $tag$, where the tag is empty or an identifier; $1 is a parameter
+                let tag_end = bytes[i + 1..]
+                    .iter()
+                    .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))
+                    .map(|offset| i + 1 + offset);
+                if let Some(tag_end) = tag_end
+                    && bytes[tag_end] == b'$'
+                    && !bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit())
+                {
+                    let tag = &bytes[i..=tag_end];
+                    let body = tag_end + 1;
+                    i = match bytes[body..]
+                        .windows(tag.len())
+                        .position(|window| window == tag)
+                    {
+                        Some(offset) => body + offset + tag.len() - 1,
+                        None => bytes.len(),
+                    };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Accept a single SELECT (or WITH ... SELECT) statement
+fn validate_select_statement(sql: &str) -> Result<&str> {
+    let statement = sql.trim().trim_end_matches(';').trim_end();
+    if statement.is_empty() {
+        return Err(anyhow!("Query cannot be empty"));
+    }
+    // Preparing the query also refuses more than one statement; this gives a clearer error
+    if has_statement_separator(statement) {
+        return Err(anyhow!("Only a single statement is allowed"));
+    }
+
+    let keyword = statement
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    if keyword != "SELECT" && keyword != "WITH" {
+        return Err(anyhow!("Only SELECT statements are allowed"));
+    }
+
+    Ok(statement)
+}
+
+/// Runs ad-hoc SQL on its own pool. The pool must log in as a restricted role that only holds
+/// USAGE and SELECT on workspace schemas, not on the `gridwalk` metadata schema, and that is
+/// not a member of any more privileged role. Privileges are never switched inside a query's
+/// transaction, so a query has nothing to escalate back to. Only available with the
+/// `sql-query` feature.
+#[derive(Debug, Clone)]
+pub struct SqlQueryConnector {
+    pool: Arc<PgPool>,
+}
+
+impl SqlQueryConnector {
+    /// Connect with `config`, whose `user` is the restricted role
+    pub async fn new(config: PostgresConfig) -> Result<Self> {
+        let pool = config
+            .pool_options()
+            .connect(&config.connection_url(true))
+            .await
+            .map_err(|e| anyhow!("Failed to create connection pool: {}", e))?;
+
+        Ok(SqlQueryConnector {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Create the connector without contacting the database, as
+    /// [`PostgisConnector::connect_lazy`](super::PostgisConnector::connect_lazy) does
+    pub fn connect_lazy(config: PostgresConfig) -> Result<Self> {
+        let pool = config
+            .pool_options()
+            .connect_lazy(&config.connection_url(true))
+            .map_err(|e| anyhow!("Failed to create connection pool: {}", e))?;
+
+        Ok(SqlQueryConnector {
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Run an ad-hoc SELECT against the layers of workspace schema `namespace` inside a
+    /// read-only transaction with a statement timeout and row limit. `search_path` is limited
+    /// to the workspace schema (and `public`, for PostGIS functions); what the query can reach
+    /// is bounded by the privileges of the pool's login role.
+    pub async fn query_sql(
+        &self,
+        namespace: &str,
+        sql: &str,
+        options: &SqlQueryOptions,
+    ) -> Result<SqlQueryResult> {
+        let statement = validate_select_statement(sql)?;
+        let search_path = format!("{}, public", quote_identifier(namespace)?);
+        let max_rows = options.max_rows as i64;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        sqlx::query("SET TRANSACTION READ ONLY")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to make transaction read-only: {}", e))?;
+        sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+            .bind(format!("{}ms", options.statement_timeout.as_millis()))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to set statement timeout: {}", e))?;
+        // Equivalent to SET LOCAL, with the values bound rather than interpolated
+        sqlx::query("SELECT set_config('search_path', $1, true)")
+            .bind(&search_path)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to set search path: {}", e))?;
+
+        // Column order and types come from preparing the query; values are read as JSON objects
+        let prepared_sql = format!("SELECT * FROM ({}) q", statement);
+        let prepared = (&mut *tx)
+            .prepare(&prepared_sql)
+            .await
+            .map_err(|e| anyhow!("Invalid query: {}", e))?;
+        let columns: Vec<SqlColumn> = prepared
+            .columns()
+            .iter()
+            .map(|column| SqlColumn {
+                name: column.name().to_string(),
+                type_name: column.type_info().name().to_string(),
+            })
+            .collect();
+
+        let json_sql = format!("SELECT to_jsonb(q) FROM ({}) q LIMIT $1", statement);
+        debug!("Executing ad-hoc SQL: {}", json_sql);
+        let mut rows =
+            sqlx::query_as::<_, (Json<serde_json::Map<String, serde_json::Value>>,)>(&json_sql)
+                .bind(max_rows + 1)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| anyhow!("Query failed: {}", e))?;
+
+        tx.rollback()
+            .await
+            .map_err(|e| anyhow!("Failed to close transaction: {}", e))?;
+
+        let truncated = rows.len() as i64 > max_rows;
+        rows.truncate(options.max_rows as usize);
+
+        let rows = rows
+            .into_iter()
+            .map(|(row,)| {
+                let mut row = row.0;
+                columns
+                    .iter()
+                    .map(|column| row.remove(&column.name).unwrap_or(serde_json::Value::Null))
+                    .collect()
+            })
+            .collect();
+
+        Ok(SqlQueryResult {
+            columns,
+            rows,
+            truncated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semicolons_inside_literals_and_comments() {
+        assert!(validate_select_statement("SELECT 'a;b'").is_ok());
+        assert!(validate_select_statement("SELECT 'it''s; fine'").is_ok());
+        assert!(validate_select_statement("SELECT \"odd;name\" FROM t").is_ok());
+        assert!(validate_select_statement("SELECT $$;$$, $tag$ $$; $tag$").is_ok());
+        assert!(validate_select_statement("SELECT 1 -- done;\n").is_ok());
+        assert!(validate_select_statement("SELECT /* a /* ; */ ; */ 1").is_ok());
+        assert!(validate_select_statement("SELECT E'\\';'").is_ok());
+        assert!(validate_select_statement("SELECT 1;").is_ok());
+    }
+
+    #[test]
+    fn multiple_statements() {
+        assert!(validate_select_statement("SELECT 1; DROP TABLE t").is_err());
+        assert!(validate_select_statement("SELECT 'a'; SELECT 'b'").is_err());
+        assert!(validate_select_statement("SELECT E'\\''; DROP TABLE t").is_err());
+        assert!(validate_select_statement("SELECT $1; DROP TABLE t").is_err());
+        assert!(validate_select_statement("SELECT 1 -- x\n; DROP TABLE t").is_err());
+    }
+
+    #[test]
+    fn select_only() {
+        assert!(validate_select_statement("").is_err());
+        assert!(validate_select_statement("DELETE FROM t").is_err());
+        assert!(validate_select_statement("with q AS (SELECT 1) SELECT * FROM q").is_ok());
+    }
+}
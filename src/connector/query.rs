@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow};
+use gdal::vector::ToGdal;
+use serde::{Deserialize, Serialize};
+
+/// Axis-aligned bounding box in the CRS identified by `srid`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bbox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    pub srid: i32,
+}
+
+impl Bbox {
+    /// Create a bounding box in WGS84 longitude/latitude
+    pub fn wgs84(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Bbox {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+            srid: 4326,
+        }
+    }
+}
+
+/// GeoJSON Feature returned by feature queries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct GeoJsonFeature {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<serde_json::Value>,
+    /// GeoJSON geometry object, or a string for the other [`GeometryEncoding`]s
+    pub geometry: Option<serde_json::Value>,
+    #[serde(default)]
+    pub properties: serde_json::Map<String, serde_json::Value>,
+}
+
+/// GeoJSON FeatureCollection returned by feature queries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection {
+    pub features: Vec<GeoJsonFeature>,
+    /// Cursor for the next page, `None` once the last page has been returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<i64>,
+}
+
+/// Attribute rows without geometry, with values in column order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttributeTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Cursor for the next page, `None` once the last page has been returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<i64>,
+}
+
+/// Keyset pagination over feature ids; avoids OFFSET scans on large layers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeaturePage {
+    pub limit: u32,
+    /// Only return features with an id greater than this cursor
+    #[serde(default)]
+    pub after: Option<i64>,
+}
+
+impl FeaturePage {
+    /// First page of up to `limit` features
+    pub fn first(limit: u32) -> Self {
+        FeaturePage { limit, after: None }
+    }
+
+    /// Page of up to `limit` features following `cursor`
+    pub fn after(limit: u32, cursor: i64) -> Self {
+        FeaturePage {
+            limit,
+            after: Some(cursor),
+        }
+    }
+}
+
+/// Encoding of feature geometry in query results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeometryEncoding {
+    /// GeoJSON geometry object
+    #[default]
+    GeoJson,
+    /// Well-known text string
+    Wkt,
+    /// Hex-encoded well-known binary
+    Wkb,
+    /// Hex-encoded extended WKB, carrying the SRID
+    Ewkb,
+}
+
+/// CRS and encoding of the geometry returned by feature queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureOutput {
+    /// Output CRS, EPSG:4326 when not set
+    #[serde(default)]
+    pub srid: Option<i32>,
+    #[serde(default)]
+    pub encoding: GeometryEncoding,
+}
+
+impl FeatureOutput {
+    /// Return geometry in the CRS identified by `srid`
+    pub fn with_srid(mut self, srid: i32) -> Self {
+        self.srid = Some(srid);
+        self
+    }
+
+    /// Return geometry using `encoding`
+    pub fn with_encoding(mut self, encoding: GeometryEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+/// Geometry supplied by the caller as the operand of a spatial query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QueryGeometry {
+    /// Well-known text in the CRS identified by `srid`
+    Wkt { wkt: String, srid: i32 },
+    /// GeoJSON geometry object in EPSG:4326
+    GeoJson(serde_json::Value),
+}
+
+impl QueryGeometry {
+    /// Convert a `geo-types` geometry in the CRS identified by `srid`
+    pub fn from_geo_types(geometry: &geo_types::Geometry<f64>, srid: i32) -> Result<Self> {
+        let wkt = geometry
+            .to_gdal()
+            .and_then(|geometry| geometry.wkt())
+            .map_err(|e| anyhow!("Failed to convert geometry to WKT: {}", e))?;
+        Ok(QueryGeometry::Wkt { wkt, srid })
+    }
+}
+
+/// Spatial relationship between layer features and a [`QueryGeometry`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "predicate", rename_all = "snake_case")]
+pub enum SpatialPredicate {
+    /// Feature intersects the query geometry
+    Intersects,
+    /// Feature lies completely within the query geometry
+    Within,
+    /// Feature completely contains the query geometry
+    Contains,
+    /// Feature is within `distance` metres of the query geometry
+    DWithin { distance: f64 },
+}
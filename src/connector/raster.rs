@@ -0,0 +1,355 @@
+use anyhow::{Result, anyhow};
+use gdal::raster::{Buffer, ResampleAlg};
+use gdal::spatial_ref::SpatialRef;
+use gdal::{Dataset, DriverManager};
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+
+/// Options controlling how a raster tile is rendered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterTileOptions {
+    /// Tiling scheme used to interpret z/x/y
+    #[serde(default)]
+    pub grid: crate::TileGrid,
+    /// Tile width and height in pixels
+    #[serde(default = "default_tile_size")]
+    pub tile_size: u32,
+    /// Source bands, numbered from 1, drawn as grey (one band) or red, green and blue (three
+    /// bands), e.g. `[4, 3, 2]` for a false colour composite. Empty uses bands 1-3 of a
+    /// multi-band raster, or band 1 as grey.
+    #[serde(default)]
+    pub bands: Vec<u32>,
+    /// `(min, max)` value range stretched to 0-255 for each entry of `bands`. Missing ranges
+    /// use the band's statistics, or its full range for 8-bit bands.
+    #[serde(default)]
+    pub rescale: Vec<(f64, f64)>,
+    /// How source pixels are resampled to the tile. Keep `Nearest` or `Mode` for categorical
+    /// rasters.
+    #[serde(default)]
+    pub resampling: crate::Resampling,
+    /// Value drawn transparent in every band, replacing the bands' own NoData values
+    #[serde(default)]
+    pub no_data: Option<f64>,
+    /// Draw a band computed from this expression as grey instead of `bands`, stretched by the
+    /// first `rescale` range or the tile's own value range
+    #[serde(default)]
+    pub expression: Option<crate::BandExpression>,
+}
+
+fn default_tile_size() -> u32 {
+    256
+}
+
+impl Default for RasterTileOptions {
+    fn default() -> Self {
+        RasterTileOptions {
+            grid: crate::TileGrid::default(),
+            tile_size: default_tile_size(),
+            bands: Vec::new(),
+            rescale: Vec::new(),
+            resampling: crate::Resampling::default(),
+            no_data: None,
+            expression: None,
+        }
+    }
+}
+
+/// A source band drawn into one tile channel
+struct BandStretch {
+    index: usize,
+    min: f64,
+    max: f64,
+    no_data: Option<f64>,
+}
+
+impl BandStretch {
+    /// Whether `value` is NoData, or NaN, and so drawn transparent
+    fn is_no_data(&self, value: f64) -> bool {
+        value.is_nan() || self.no_data.is_some_and(|no_data| value == no_data)
+    }
+
+    fn stretch(&self, value: f64) -> u8 {
+        let span = if self.max > self.min {
+            self.max - self.min
+        } else {
+            1.0
+        };
+        ((value - self.min) / span * 255.0)
+            .clamp(0.0, 255.0)
+            .round() as u8
+    }
+}
+
+/// Bands to draw and how to stretch each, resolving the defaults of `options`
+fn band_mapping(dataset: &Dataset, options: &RasterTileOptions) -> Result<Vec<BandStretch>> {
+    if let Some(expression) = &options.expression {
+        // Inputs of an expression are only read, never stretched
+        return expression
+            .bands()
+            .into_iter()
+            .map(|index| {
+                let band = dataset
+                    .rasterband(index)
+                    .map_err(|e| anyhow!("Raster has no band {}: {}", index, e))?;
+                Ok(BandStretch {
+                    index,
+                    min: 0.0,
+                    max: 0.0,
+                    no_data: options.no_data.or(band.no_data_value()),
+                })
+            })
+            .collect();
+    }
+
+    let bands: Vec<usize> = match options.bands.as_slice() {
+        [] if dataset.raster_count() >= 3 => vec![1, 2, 3],
+        [] => vec![1],
+        bands @ ([_] | [_, _, _]) => bands.iter().map(|band| *band as usize).collect(),
+        bands => {
+            return Err(anyhow!(
+                "Expected 1 or 3 bands for a raster tile, got {}",
+                bands.len()
+            ));
+        }
+    };
+
+    bands
+        .iter()
+        .enumerate()
+        .map(|(position, index)| {
+            let band = dataset
+                .rasterband(*index)
+                .map_err(|e| anyhow!("Raster has no band {}: {}", index, e))?;
+            let (min, max) = match options.rescale.get(position) {
+                Some(range) => *range,
+                None if band.band_type() == gdal::raster::GdalDataType::UInt8 => (0.0, 255.0),
+                None => band
+                    .get_statistics(true, true)?
+                    .map(|stats| (stats.min, stats.max))
+                    .unwrap_or((0.0, 255.0)),
+            };
+            Ok(BandStretch {
+                index: *index,
+                min,
+                max,
+                no_data: options.no_data.or(band.no_data_value()),
+            })
+        })
+        .collect()
+}
+
+/// GDAL raster IO algorithm for a resampling method
+fn resample_alg(resampling: crate::Resampling) -> ResampleAlg {
+    match resampling {
+        crate::Resampling::Nearest => ResampleAlg::NearestNeighbour,
+        crate::Resampling::Bilinear => ResampleAlg::Bilinear,
+        crate::Resampling::Cubic => ResampleAlg::Cubic,
+        crate::Resampling::Average => ResampleAlg::Average,
+        crate::Resampling::Mode => ResampleAlg::Mode,
+    }
+}
+
+/// GDAL warp algorithm for a resampling method
+fn warp_alg(resampling: crate::Resampling) -> gdal_sys::GDALResampleAlg::Type {
+    match resampling {
+        crate::Resampling::Nearest => gdal_sys::GDALResampleAlg::GRA_NearestNeighbour,
+        crate::Resampling::Bilinear => gdal_sys::GDALResampleAlg::GRA_Bilinear,
+        crate::Resampling::Cubic => gdal_sys::GDALResampleAlg::GRA_Cubic,
+        crate::Resampling::Average => gdal_sys::GDALResampleAlg::GRA_Average,
+        crate::Resampling::Mode => gdal_sys::GDALResampleAlg::GRA_Mode,
+    }
+}
+
+/// Virtual dataset warping `dataset` into `srid`. The result reads from the source on demand,
+/// so the source must stay open for as long as it is used.
+pub(crate) fn warped_vrt(
+    dataset: &Dataset,
+    srid: crate::Srid,
+    resampling: crate::Resampling,
+) -> Result<Dataset> {
+    let target = SpatialRef::from_epsg(srid.code())?.to_wkt()?;
+    let target = CString::new(target)?;
+    let handle = unsafe {
+        gdal_sys::GDALAutoCreateWarpedVRT(
+            dataset.c_dataset(),
+            std::ptr::null(),
+            target.as_ptr(),
+            warp_alg(resampling),
+            0.125,
+            std::ptr::null(),
+        )
+    };
+    if handle.is_null() {
+        return Err(anyhow!("Failed to warp raster to EPSG:{}", srid));
+    }
+    Ok(unsafe { Dataset::from_c_dataset(handle) })
+}
+
+/// Warp a raster into `srid`, returning an in-memory copy independent of the source. The
+/// source must declare its CRS.
+pub fn reproject_raster(
+    dataset: &Dataset,
+    srid: crate::Srid,
+    resampling: crate::Resampling,
+) -> Result<Dataset> {
+    let warped = warped_vrt(dataset, srid, resampling)?;
+    let mem = DriverManager::get_driver_by_name("MEM")?;
+    warped
+        .create_copy(&mem, "", &Default::default())
+        .map_err(|e| anyhow!("Failed to reproject raster: {}", e))
+}
+
+/// Encode a tile-sized RGBA buffer as PNG through GDAL's PNG driver
+fn encode_png(rgba: [Vec<u8>; 4], size: usize) -> Result<Vec<u8>> {
+    let mem = DriverManager::get_driver_by_name("MEM")?;
+    let mut tile = mem.create_with_band_type::<u8, _>("", size, size, 4)?;
+    for (index, channel) in rgba.into_iter().enumerate() {
+        let mut buffer = Buffer::new((size, size), channel);
+        tile.rasterband(index + 1)?
+            .write((0, 0), (size, size), &mut buffer)?;
+    }
+
+    let path = format!("/vsimem/{}.png", uuid::Uuid::new_v4());
+    let png = DriverManager::get_driver_by_name("PNG")?;
+    tile.create_copy(&png, &path, &Default::default())?;
+    Ok(gdal::vsi::get_vsi_mem_file_bytes_owned(&path)?)
+}
+
+/// Render tile z/x/y of a raster as an RGBA PNG, stretching the chosen bands to 8 bits. Areas
+/// the raster does not cover, and pixels that are NoData in any drawn band, are transparent.
+/// Rasters in another CRS than the tile grid, or with a rotated geotransform, are warped on the
+/// fly. Blocking, so call it from `spawn_blocking` in async code.
+pub fn render_raster_tile(
+    dataset: &Dataset,
+    z: u32,
+    x: u32,
+    y: u32,
+    options: &RasterTileOptions,
+) -> Result<Vec<u8>> {
+    let bounds = options.grid.tile_bounds(z, x, y)?;
+    let size = options.tile_size as usize;
+    if size == 0 {
+        return Err(anyhow!("Tile size must be positive"));
+    }
+
+    // Stretch ranges come from the source, as statistics of a warped view scan all of it
+    let bands = band_mapping(dataset, options)?;
+    let srid = dataset
+        .spatial_ref()
+        .ok()
+        .and_then(|srs| srs.auth_code().ok());
+    let grid_srid = options.grid.srid();
+    let rotated = dataset
+        .geo_transform()
+        .is_ok_and(|gt| gt[2] != 0.0 || gt[4] != 0.0);
+    let warped;
+    let dataset = if srid == Some(i32::from(grid_srid)) && !rotated {
+        dataset
+    } else {
+        warped = warped_vrt(dataset, grid_srid, options.resampling)?;
+        &warped
+    };
+    let gt = dataset.geo_transform()?;
+    let (raster_width, raster_height) = dataset.raster_size();
+
+    // Tile bounds in source pixel coordinates, then the part the raster covers
+    let to_pixel = |map_x: f64, map_y: f64| ((map_x - gt[0]) / gt[1], (map_y - gt[3]) / gt[5]);
+    let (left, top) = to_pixel(bounds.min_x, bounds.max_y);
+    let (right, bottom) = to_pixel(bounds.max_x, bounds.min_y);
+    let tile_pixel = (right - left) / size as f64;
+    let tile_line = (bottom - top) / size as f64;
+    let read_left = left.max(0.0);
+    let read_top = top.max(0.0);
+    let read_right = right.min(raster_width as f64);
+    let read_bottom = bottom.min(raster_height as f64);
+
+    let mut rgba = [
+        vec![0u8; size * size],
+        vec![0u8; size * size],
+        vec![0u8; size * size],
+        vec![0u8; size * size],
+    ];
+    if read_right <= read_left || read_bottom <= read_top {
+        return encode_png(rgba, size);
+    }
+
+    // Destination rectangle inside the tile for the covered source window
+    let dest_x = ((read_left - left) / tile_pixel).round() as usize;
+    let dest_y = ((read_top - top) / tile_line).round() as usize;
+    let dest_width = (((read_right - left) / tile_pixel).round() as usize)
+        .min(size)
+        .saturating_sub(dest_x);
+    let dest_height = (((read_bottom - top) / tile_line).round() as usize)
+        .min(size)
+        .saturating_sub(dest_y);
+    if dest_width == 0 || dest_height == 0 {
+        return encode_png(rgba, size);
+    }
+    let window = (read_left.floor() as isize, read_top.floor() as isize);
+    let window_size = (
+        ((read_right.ceil() as isize - window.0) as usize).max(1),
+        ((read_bottom.ceil() as isize - window.1) as usize).max(1),
+    );
+
+    let mut channels = Vec::with_capacity(bands.len());
+    for band in &bands {
+        let buffer = dataset.rasterband(band.index)?.read_as::<f64>(
+            window,
+            window_size,
+            (dest_width, dest_height),
+            Some(resample_alg(options.resampling)),
+        )?;
+        channels.push(buffer.into_shape_and_vec().1);
+    }
+    let (bands, channels) = match &options.expression {
+        Some(expression) => {
+            let indexes: Vec<usize> = bands.iter().map(|band| band.index).collect();
+            let no_data: Vec<Option<f64>> = bands.iter().map(|band| band.no_data).collect();
+            let values = super::band_math::evaluate_pixels(
+                expression,
+                &indexes,
+                &no_data,
+                &channels,
+                dest_width * dest_height,
+            );
+            let (min, max) = options.rescale.first().copied().unwrap_or_else(|| {
+                values
+                    .iter()
+                    .filter(|value| !value.is_nan())
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                        (min.min(*value), max.max(*value))
+                    })
+            });
+            let band = BandStretch {
+                index: 0,
+                min,
+                max,
+                no_data: None,
+            };
+            (vec![band], vec![values])
+        }
+        None => (bands, channels),
+    };
+
+    for row in 0..dest_height {
+        for column in 0..dest_width {
+            let source = row * dest_width + column;
+            let target = (dest_y + row) * size + dest_x + column;
+            if bands
+                .iter()
+                .zip(&channels)
+                .any(|(band, values)| band.is_no_data(values[source]))
+            {
+                continue;
+            }
+            for (channel, output) in rgba.iter_mut().take(3).enumerate() {
+                // A single band is drawn as grey
+                let position = channel.min(bands.len() - 1);
+                output[target] = bands[position].stretch(channels[position][source]);
+            }
+            rgba[3][target] = 255;
+        }
+    }
+
+    encode_png(rgba, size)
+}
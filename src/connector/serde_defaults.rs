@@ -0,0 +1,10 @@
+// `#[serde(default = "...")]` values shared by connector options
+
+/// First band of a raster; bands are numbered from 1
+pub(crate) fn default_band() -> u32 {
+    1
+}
+
+pub(crate) fn default_true() -> bool {
+    true
+}
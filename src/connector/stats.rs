@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// One equal-width bucket of a field histogram
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: i64,
+}
+
+/// Summary statistics for a numeric field, used for legends and class breaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldStats {
+    pub field: String,
+    /// Number of non-null values
+    pub count: i64,
+    pub null_count: i64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub histogram: Vec<HistogramBin>,
+}
+
+impl FieldStats {
+    /// Split `[min, max]` into `bins` equal-width buckets using counts indexed by bucket
+    pub fn build_histogram(min: f64, max: f64, counts: &[i64]) -> Vec<HistogramBin> {
+        let width = (max - min) / counts.len().max(1) as f64;
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| HistogramBin {
+                lower: min + width * i as f64,
+                upper: if i + 1 == counts.len() {
+                    max
+                } else {
+                    min + width * (i + 1) as f64
+                },
+                count: *count,
+            })
+            .collect()
+    }
+}
+
+/// A distinct value of a field and how many features carry it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistinctValue {
+    pub value: serde_json::Value,
+    pub count: i64,
+}
+
+/// Number of features of one geometry type, e.g. `POLYGON` or `MULTIPOLYGON`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeometryTypeCount {
+    pub geometry_type: String,
+    pub count: i64,
+}
+
+/// Number of histogram bins computed for each field in [`LayerStats`]
+pub const LAYER_STATS_HISTOGRAM_BINS: u32 = 10;
+
+/// Layer-wide statistics computed after ingest and stored with the layer metadata, so UIs can
+/// show layer information without scanning the data table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerStats {
+    pub feature_count: u64,
+    /// Extent in EPSG:4326, `None` for an empty layer
+    pub extent: Option<crate::Bbox>,
+    pub geometry_types: Vec<GeometryTypeCount>,
+    pub fields: Vec<FieldStats>,
+    pub computed_at: chrono::DateTime<chrono::Utc>,
+}
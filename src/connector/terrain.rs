@@ -0,0 +1,193 @@
+use super::serde_defaults::{default_band, default_true};
+use anyhow::{Result, anyhow};
+use gdal::cpl::CslStringList;
+use gdal::vector::{LayerAccess, LayerOptions};
+use gdal::{Dataset, DriverManager};
+use gdal_sys::{OGRFieldType, OGRwkbGeometryType};
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+
+/// Terrain derivative computed from a DEM by [`derive_terrain`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerrainDerivative {
+    /// Steepest gradient, in degrees or as a percentage
+    Slope {
+        #[serde(default)]
+        percent: bool,
+    },
+    /// Direction the slope faces, in degrees clockwise from north; flat areas are NoData
+    Aspect,
+    /// Terrain Ruggedness Index, the mean difference between a cell and its neighbours
+    Tri,
+}
+
+impl TerrainDerivative {
+    /// Processing mode name of `gdaldem`
+    fn gdal_name(self) -> &'static str {
+        match self {
+            TerrainDerivative::Slope { .. } => "slope",
+            TerrainDerivative::Aspect => "aspect",
+            TerrainDerivative::Tri => "TRI",
+        }
+    }
+}
+
+/// Options for [`derive_terrain`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerrainOptions {
+    /// Elevation band, numbered from 1
+    #[serde(default = "default_band")]
+    pub band: u32,
+    /// Ratio of horizontal to vertical units, e.g. `111120` for a DEM in degrees with
+    /// elevations in metres. Projected DEMs with metre elevations need none.
+    #[serde(default)]
+    pub scale: Option<f64>,
+    /// Compute edge cells from the neighbours that exist instead of leaving them NoData
+    #[serde(default = "default_true")]
+    pub compute_edges: bool,
+}
+
+impl Default for TerrainOptions {
+    fn default() -> Self {
+        TerrainOptions {
+            band: default_band(),
+            scale: None,
+            compute_edges: true,
+        }
+    }
+}
+
+/// Compute slope, aspect or TRI of a DEM as a single band Float32 raster in memory, with the
+/// DEM's georeference. The result can be drawn with
+/// [`render_raster_tile`](crate::render_raster_tile) or stored with
+/// [`PostgisConnector::ingest_raster`](crate::connector::postgis::PostgisConnector::ingest_raster).
+/// Blocking.
+pub fn derive_terrain(
+    dataset: &Dataset,
+    derivative: TerrainDerivative,
+    options: &TerrainOptions,
+) -> Result<Dataset> {
+    let mut args = CslStringList::new();
+    args.add_string("-of")?;
+    args.add_string("MEM")?;
+    args.add_string("-b")?;
+    args.add_string(&options.band.to_string())?;
+    if let Some(scale) = options.scale {
+        args.add_string("-s")?;
+        args.add_string(&scale.to_string())?;
+    }
+    if options.compute_edges {
+        args.add_string("-compute_edges")?;
+    }
+    if let TerrainDerivative::Slope { percent: true } = derivative {
+        args.add_string("-p")?;
+    }
+
+    let processing = CString::new(derivative.gdal_name())?;
+    let destination = CString::new("")?;
+    let dem_options =
+        unsafe { gdal_sys::GDALDEMProcessingOptionsNew(args.as_ptr(), std::ptr::null_mut()) };
+    if dem_options.is_null() {
+        return Err(anyhow!("Invalid terrain options"));
+    }
+    let output = unsafe {
+        gdal_sys::GDALDEMProcessing(
+            destination.as_ptr(),
+            dataset.c_dataset(),
+            processing.as_ptr(),
+            std::ptr::null(),
+            dem_options,
+            std::ptr::null_mut(),
+        )
+    };
+    unsafe { gdal_sys::GDALDEMProcessingOptionsFree(dem_options) };
+    if output.is_null() {
+        return Err(anyhow!(
+            "Failed to compute {} of the DEM",
+            derivative.gdal_name()
+        ));
+    }
+    Ok(unsafe { Dataset::from_c_dataset(output) })
+}
+
+/// Layer of the dataset returned by [`generate_contours`]
+pub const CONTOUR_LAYER: &str = "contours";
+
+/// Field of [`CONTOUR_LAYER`] holding each line's level
+pub const CONTOUR_LEVEL_FIELD: &str = "level";
+
+/// Options for [`generate_contours`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContourOptions {
+    /// Band to contour, numbered from 1
+    #[serde(default = "default_band")]
+    pub band: u32,
+    /// Level the intervals are counted from, e.g. `5` with an interval of `10` gives lines at
+    /// 5, 15, 25, ...
+    #[serde(default)]
+    pub base: f64,
+}
+
+impl Default for ContourOptions {
+    fn default() -> Self {
+        ContourOptions {
+            band: default_band(),
+            base: 0.0,
+        }
+    }
+}
+
+/// Trace contour lines every `interval` units of a raster band into an in-memory vector
+/// dataset with a single LineString layer, [`CONTOUR_LAYER`], carrying each line's value in
+/// [`CONTOUR_LEVEL_FIELD`]. The dataset goes through the same schema extraction and loading as
+/// an uploaded vector file. NoData cells are left out. Blocking.
+pub fn generate_contours(
+    dataset: &Dataset,
+    interval: f64,
+    options: &ContourOptions,
+) -> Result<Dataset> {
+    if !(interval > 0.0 && interval.is_finite()) {
+        return Err(anyhow!(
+            "Contour interval must be positive, got {}",
+            interval
+        ));
+    }
+    let band = dataset
+        .rasterband(options.band as usize)
+        .map_err(|e| anyhow!("Raster has no band {}: {}", options.band, e))?;
+
+    let driver = DriverManager::get_driver_by_name("Memory")?;
+    let mut contour_options = CslStringList::new();
+    contour_options.set_name_value("LEVEL_INTERVAL", &interval.to_string())?;
+    contour_options.set_name_value("LEVEL_BASE", &options.base.to_string())?;
+    contour_options.set_name_value("ELEV_FIELD", "0")?;
+    if let Some(no_data) = band.no_data_value() {
+        contour_options.set_name_value("NODATA", &no_data.to_string())?;
+    }
+
+    let mut output = driver.create_vector_only("")?;
+    let srs = dataset.spatial_ref().ok();
+    {
+        let layer = output.create_layer(LayerOptions {
+            name: CONTOUR_LAYER,
+            srs: srs.as_ref(),
+            ty: OGRwkbGeometryType::wkbLineString,
+            options: None,
+        })?;
+        layer.create_defn_fields(&[(CONTOUR_LEVEL_FIELD, OGRFieldType::OFTReal)])?;
+        let result = unsafe {
+            gdal_sys::GDALContourGenerateEx(
+                band.c_rasterband(),
+                layer.c_layer() as *mut std::ffi::c_void,
+                contour_options.as_ptr() as _,
+                None,
+                std::ptr::null_mut(),
+            )
+        };
+        if result != gdal_sys::CPLErr::CE_None {
+            return Err(anyhow!("Failed to generate contours"));
+        }
+    }
+    Ok(output)
+}
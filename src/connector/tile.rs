@@ -0,0 +1,266 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Half the width of the EPSG:3857 world extent in metres
+const WEB_MERCATOR_HALF_EXTENT: f64 = 20037508.342789244;
+
+/// Tile bounds expressed in the CRS of the tile grid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TileBounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+/// Custom tile matrix set, e.g. a national grid such as EPSG:27700
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileMatrixSet {
+    /// SRID of the CRS the tiles are cut in
+    pub srid: crate::Srid,
+    /// Top-left corner of the grid
+    pub origin_x: f64,
+    pub origin_y: f64,
+    /// Tile width and height in pixels
+    pub tile_size: u32,
+    /// Ground units per pixel, indexed by zoom level
+    pub resolutions: Vec<f64>,
+}
+
+/// Tiling scheme used to turn z/x/y into a bounding box
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum TileGrid {
+    /// Standard XYZ grid in EPSG:3857
+    #[default]
+    WebMercatorQuad,
+    /// OGC WorldCRS84Quad in EPSG:4326, two tiles wide at zoom 0
+    WorldCrs84Quad,
+    /// User supplied tile matrix set
+    Custom(TileMatrixSet),
+}
+
+impl TileGrid {
+    /// SRID of the CRS tiles are produced in
+    pub fn srid(&self) -> crate::Srid {
+        match self {
+            TileGrid::WebMercatorQuad => crate::Srid::EPSG3857,
+            TileGrid::WorldCrs84Quad => crate::Srid::EPSG4326,
+            TileGrid::Custom(matrix_set) => matrix_set.srid,
+        }
+    }
+
+    /// Bounds of tile z/x/y in the grid CRS, with y counted from the top
+    pub fn tile_bounds(&self, z: u32, x: u32, y: u32) -> Result<TileBounds> {
+        let (origin_x, origin_y, span, tiles_wide, tiles_high) = match self {
+            TileGrid::WebMercatorQuad => {
+                let count = 2f64.powi(z as i32);
+                let span = 2.0 * WEB_MERCATOR_HALF_EXTENT / count;
+                (
+                    -WEB_MERCATOR_HALF_EXTENT,
+                    WEB_MERCATOR_HALF_EXTENT,
+                    span,
+                    Some(count),
+                    Some(count),
+                )
+            }
+            TileGrid::WorldCrs84Quad => {
+                let count = 2f64.powi(z as i32);
+                (-180.0, 90.0, 180.0 / count, Some(2.0 * count), Some(count))
+            }
+            TileGrid::Custom(matrix_set) => {
+                let resolution = matrix_set.resolutions.get(z as usize).ok_or_else(|| {
+                    anyhow!(
+                        "Zoom level {} is not defined in the tile matrix set (max {})",
+                        z,
+                        matrix_set.resolutions.len().saturating_sub(1)
+                    )
+                })?;
+                (
+                    matrix_set.origin_x,
+                    matrix_set.origin_y,
+                    resolution * matrix_set.tile_size as f64,
+                    None,
+                    None,
+                )
+            }
+        };
+
+        if tiles_wide.is_some_and(|count| x as f64 >= count)
+            || tiles_high.is_some_and(|count| y as f64 >= count)
+        {
+            return Err(anyhow!("Tile {}/{}/{} is outside the tile grid", z, x, y));
+        }
+
+        let min_x = origin_x + x as f64 * span;
+        let max_y = origin_y - y as f64 * span;
+        Ok(TileBounds {
+            min_x,
+            min_y: max_y - span,
+            max_x: min_x + span,
+            max_y,
+        })
+    }
+}
+
+/// Encoded tile together with a stable content hash suitable for HTTP caching
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub data: Vec<u8>,
+    /// Hex encoded xxh3 hash of the layer version and tile bytes
+    pub hash: String,
+}
+
+impl Tile {
+    /// Wrap tile bytes, hashing them together with an optional layer version
+    pub fn new(data: Vec<u8>, layer_version: Option<&str>) -> Self {
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        if let Some(version) = layer_version {
+            hasher.update(version.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.update(&data);
+        Tile {
+            data,
+            hash: format!("{:016x}", hasher.digest()),
+        }
+    }
+
+    /// Quoted hash for use as an HTTP `ETag` header value
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.hash)
+    }
+
+    /// Check an `If-None-Match` header value against this tile
+    pub fn matches_etag(&self, if_none_match: &str) -> bool {
+        if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag.trim_matches('"') == self.hash)
+    }
+}
+
+/// Store for rendered tiles keyed by layer and tile coordinates, implemented by host applications
+#[async_trait]
+pub trait TileCache: Send + Sync {
+    async fn get(&self, layer_id: &Uuid, z: u32, x: u32, y: u32) -> Result<Option<Tile>>;
+    async fn put(&self, layer_id: &Uuid, z: u32, x: u32, y: u32, tile: &Tile) -> Result<()>;
+
+    /// Remove every cached tile of a layer
+    async fn evict_layer(&self, layer_id: &Uuid) -> Result<()>;
+}
+
+/// Number of UTFGrid cells along each side of a tile (256px at resolution 4)
+pub const UTFGRID_SIZE: u32 = 64;
+
+/// UTFGrid interaction tile, serializable straight to the UTFGrid 1.3 JSON format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtfGrid {
+    pub grid: Vec<String>,
+    pub keys: Vec<String>,
+    pub data: HashMap<String, serde_json::Value>,
+}
+
+impl UtfGrid {
+    /// Build a grid from the feature key covering each cell, in row-major order
+    pub fn from_cells(cells: &[Option<String>], key_field: &str) -> Self {
+        let mut keys = vec![String::new()];
+        let mut key_index: HashMap<&str, u32> = HashMap::new();
+        let mut data = HashMap::new();
+        let mut grid = Vec::with_capacity(UTFGRID_SIZE as usize);
+
+        for row in cells.chunks(UTFGRID_SIZE as usize) {
+            let mut line = String::with_capacity(row.len());
+            for cell in row {
+                let id = match cell {
+                    Some(key) => *key_index.entry(key.as_str()).or_insert_with(|| {
+                        keys.push(key.clone());
+                        data.insert(key.clone(), serde_json::json!({ key_field: key }));
+                        (keys.len() - 1) as u32
+                    }),
+                    None => 0,
+                };
+                line.push(Self::encode_id(id));
+            }
+            grid.push(line);
+        }
+
+        UtfGrid { grid, keys, data }
+    }
+
+    /// Encode a key index as a UTFGrid character, skipping `"` and `\`
+    fn encode_id(id: u32) -> char {
+        let mut code = id + 32;
+        if code >= 34 {
+            code += 1;
+        }
+        if code >= 92 {
+            code += 1;
+        }
+        char::from_u32(code).unwrap_or(' ')
+    }
+}
+
+/// Layer entry in a TileJSON `vector_layers` list
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorLayer {
+    pub id: String,
+    /// Field name to a description of its type
+    pub fields: HashMap<String, String>,
+}
+
+/// TileJSON 3.0.0 document describing a tiled layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileJson {
+    pub tilejson: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub tiles: Vec<String>,
+    pub minzoom: u8,
+    pub maxzoom: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<[f64; 4]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<[f64; 3]>,
+    #[serde(default)]
+    pub vector_layers: Vec<VectorLayer>,
+    /// Layer style carried alongside the tile metadata (not part of the TileJSON spec)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<crate::StyleDefinition>,
+}
+
+impl TileJson {
+    /// TileJSON for a set of tile URL templates with the default 0-22 zoom range
+    pub fn new(name: impl Into<String>, tiles: Vec<String>) -> Self {
+        TileJson {
+            tilejson: "3.0.0".to_string(),
+            name: Some(name.into()),
+            tiles,
+            minzoom: 0,
+            maxzoom: 22,
+            bounds: None,
+            center: None,
+            vector_layers: Vec::new(),
+            style: None,
+        }
+    }
+
+    /// Set bounds and center from a WGS84 extent
+    pub fn with_bounds(mut self, extent: &crate::Bbox) -> Self {
+        self.bounds = Some([extent.min_x, extent.min_y, extent.max_x, extent.max_y]);
+        self.center = Some([
+            (extent.min_x + extent.max_x) / 2.0,
+            (extent.min_y + extent.max_y) / 2.0,
+            self.minzoom as f64,
+        ]);
+        self
+    }
+
+    /// Embed the layer's style so clients can render it without a second request
+    pub fn with_style(mut self, style: &crate::LayerStyle) -> Self {
+        self.style = Some(style.definition.clone());
+        self
+    }
+}
@@ -1,16 +1,19 @@
 use gdal::Dataset;
 use gdal::vector::{Layer, LayerAccess};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::Range;
 
 /// Selector for identifying a layer by either index or name
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LayerSelector {
     Index(usize),
     Name(String),
 }
 
 impl LayerSelector {
-    fn get_layer<'a>(
+    pub(crate) fn get_layer<'a>(
         &self,
         dataset: &'a Dataset,
     ) -> Result<Layer<'a>, Box<dyn std::error::Error + Send>> {
@@ -31,6 +34,131 @@ impl LayerSelector {
     }
 }
 
+/// A fixed-size window of a layer's features, ingested and committed as one unit. Serializable
+/// so each chunk can be queued as its own job payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureChunk {
+    pub layer: LayerSelector,
+    /// Position of the chunk in its plan, starting at 0
+    pub index: u64,
+    /// Features are counted in the layer's reading order from 0, not by FID, which may start
+    /// at 1 or have gaps depending on the driver
+    pub start: u64,
+    pub end: u64,
+}
+
+impl FeatureChunk {
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// Iterator over this chunk's features
+    pub fn features(
+        &self,
+        dataset: Dataset,
+    ) -> Result<FeatureIterator, Box<dyn std::error::Error + Send>> {
+        FeatureIterator::with_range(dataset, self.layer.clone(), self.range())
+    }
+
+    /// Pass each GDAL feature of this chunk to `visit`, e.g. to build insert statements for it
+    pub fn for_each_feature<F>(
+        &self,
+        dataset: &Dataset,
+        mut visit: F,
+    ) -> Result<(), Box<dyn std::error::Error + Send>>
+    where
+        F: FnMut(
+            &gdal::vector::Feature,
+            &gdal::vector::Defn,
+        ) -> Result<(), Box<dyn std::error::Error + Send>>,
+    {
+        let layer = self.layer.get_layer(dataset)?;
+        seek_feature(&layer, self.start)?;
+        for _ in self.range() {
+            match next_feature(&layer) {
+                Some(feature) => visit(&feature, layer.defn())?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position `layer` so the next sequential read returns the feature at `index` in reading order
+fn seek_feature(layer: &Layer, index: u64) -> Result<(), Box<dyn std::error::Error + Send>> {
+    let result = unsafe { gdal_sys::OGR_L_SetNextByIndex(layer.c_layer(), index as i64) };
+    if result != gdal_sys::OGRErr::OGRERR_NONE {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to seek to feature {}", index),
+        )));
+    }
+    Ok(())
+}
+
+/// Next feature in sequential reading order, `None` past the last one
+fn next_feature<'a>(layer: &'a Layer) -> Option<gdal::vector::Feature<'a>> {
+    let c_feature = unsafe { gdal_sys::OGR_L_GetNextFeature(layer.c_layer()) };
+    if c_feature.is_null() {
+        None
+    } else {
+        Some(unsafe { gdal::vector::Feature::from_c_feature(layer.defn(), c_feature) })
+    }
+}
+
+/// Split of a large layer into [`FeatureChunk`]s, so an import can resume after a restart from
+/// the chunks that were not yet committed. [`crate::queue_chunk_jobs`] queues them as jobs and
+/// [`crate::run_chunk_job`] ingests and commits each one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkPlan {
+    pub layer: LayerSelector,
+    pub feature_count: u64,
+    pub chunk_size: u64,
+}
+
+impl ChunkPlan {
+    /// Plan chunks of `chunk_size` features over the layer chosen by `layer`, by position in
+    /// reading order so every feature falls in exactly one chunk whatever its FID
+    pub fn new(
+        dataset: &Dataset,
+        layer: LayerSelector,
+        chunk_size: u64,
+    ) -> Result<Self, Box<dyn std::error::Error + Send>> {
+        if chunk_size == 0 {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Chunk size must be positive",
+            )));
+        }
+        let feature_count = layer.get_layer(dataset)?.feature_count();
+        Ok(Self {
+            layer,
+            feature_count,
+            chunk_size,
+        })
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        self.feature_count.div_ceil(self.chunk_size)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = FeatureChunk> + '_ {
+        (0..self.chunk_count()).map(|index| FeatureChunk {
+            layer: self.layer.clone(),
+            index,
+            start: index * self.chunk_size,
+            end: ((index + 1) * self.chunk_size).min(self.feature_count),
+        })
+    }
+
+    /// Chunks still to ingest, given the indexes of those already committed
+    pub fn pending(&self, completed: &[u64]) -> Vec<FeatureChunk> {
+        self.chunks()
+            .filter(|chunk| !completed.contains(&chunk.index))
+            .collect()
+    }
+}
+
 /// Represents a single feature ready for PostGIS insertion
 #[derive(Debug, Clone)]
 pub struct Feature {
@@ -56,8 +184,11 @@ pub enum FieldValue {
 pub struct FeatureIterator {
     dataset: Dataset,
     layer_selector: LayerSelector,
+    /// Position in reading order of the next feature
     current_index: u64,
     feature_count: u64,
+    /// Whether the layer's read cursor has been moved to `current_index`
+    positioned: bool,
 }
 
 impl FeatureIterator {
@@ -74,9 +205,23 @@ impl FeatureIterator {
             layer_selector,
             current_index: 0,
             feature_count,
+            positioned: false,
         })
     }
 
+    /// Iterator over the features at positions `range` in reading order only, for ingesting
+    /// one [`FeatureChunk`]. The window is clamped to the layer's feature count.
+    pub fn with_range(
+        dataset: Dataset,
+        layer_selector: LayerSelector,
+        range: Range<u64>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send>> {
+        let mut iterator = Self::new(dataset, layer_selector)?;
+        iterator.feature_count = iterator.feature_count.min(range.end);
+        iterator.current_index = range.start.min(iterator.feature_count);
+        Ok(iterator)
+    }
+
     /// Convenience constructor for layer by index
     pub fn new_by_index(
         dataset: Dataset,
@@ -105,27 +250,22 @@ impl Iterator for FeatureIterator {
             Err(e) => return Some(Err(e)), // This already returns Send-compatible error
         };
 
-        loop {
-            if self.current_index >= self.feature_count {
-                return None;
-            }
-
-            match layer.feature(self.current_index) {
-                Some(gdal_feature) => {
-                    self.current_index += 1;
-
-                    // Now we can safely access layer info since we're not using the iterator
-                    let layer_defn = layer.defn();
-                    let srid = layer.spatial_ref().and_then(|srs| srs.auth_code().ok());
-                    let feature = convert_gdal_feature(&gdal_feature, &layer_defn, srid);
-                    return Some(feature);
-                }
-                None => {
-                    self.current_index += 1;
-                    // Continue loop to try next feature
-                }
+        if self.current_index >= self.feature_count {
+            return None;
+        }
+        // Features are read sequentially; FIDs are not necessarily 0..feature_count
+        if !self.positioned {
+            if let Err(e) = seek_feature(&layer, self.current_index) {
+                return Some(Err(e));
             }
+            self.positioned = true;
         }
+        let gdal_feature = next_feature(&layer)?;
+        self.current_index += 1;
+
+        let layer_defn = layer.defn();
+        let srid = layer.spatial_ref().and_then(|srs| srs.auth_code().ok());
+        Some(convert_gdal_feature(&gdal_feature, layer_defn, srid))
     }
 }
 
@@ -164,6 +304,11 @@ fn convert_gdal_feature(
             )) as Box<dyn std::error::Error + Send>
         })? {
             Some(gdal::vector::FieldValue::StringValue(s)) => FieldValue::Text(s),
+            Some(gdal::vector::FieldValue::IntegerValue(i))
+                if crate::file::gdal_field_type_name(layer_defn, field_idx) == "Boolean" =>
+            {
+                FieldValue::Boolean(i != 0)
+            }
             Some(gdal::vector::FieldValue::IntegerValue(i)) => FieldValue::Integer(i.into()),
             Some(gdal::vector::FieldValue::Integer64Value(i)) => FieldValue::Integer(i),
             Some(gdal::vector::FieldValue::RealValue(f)) => FieldValue::Real(f),
@@ -1,8 +1,340 @@
-use crate::VectorConnector;
-use gdal::Dataset;
-use gdal::vector::LayerAccess;
+use crate::conversion::LayerSelector;
+use crate::{CoordinateDimension, CrsDefinition, VectorConnector};
+use gdal::spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef};
+use gdal::vector::{Defn, Layer, LayerAccess};
+use gdal::{Dataset, Metadata};
+use gdal_sys::{OGRFieldSubType, OGRFieldType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tokio::task;
 
+/// Longest identifier Postgres keeps; longer names are silently truncated by the server
+pub const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+/// Column holding feature ids, generated unless a source field is designated as primary key
+pub const ID_COLUMN: &str = "id";
+
+/// Geometry column name used unless [`SchemaOptions::geometry_column`] says otherwise
+pub const DEFAULT_GEOMETRY_COLUMN: &str = "geometry";
+
+/// Column names generated by the connector, which source fields must not collide with
+const GENERATED_COLUMNS: &[&str] = &[ID_COLUMN];
+
+/// `base`, or `base` with the lowest numeric suffix that is not already taken, shortened so the
+/// result stays within the identifier limit
+fn unique_column_name(base: &str, taken: &HashSet<String>) -> String {
+    let mut column = base.to_string();
+    let mut suffix = 1;
+    while taken.contains(&column) {
+        suffix += 1;
+        let tail = format!("_{}", suffix);
+        let limit = MAX_IDENTIFIER_LENGTH.saturating_sub(tail.len());
+        column = format!("{}{}", truncate_identifier(base, limit), tail);
+    }
+    column
+}
+
+/// How source field names are turned into column names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnNaming {
+    /// Keep names exactly as in the source; only collisions are suffixed
+    Preserve,
+    /// Lowercase ASCII snake_case: accents are transliterated and anything else that is not a
+    /// letter or digit becomes an underscore
+    #[default]
+    SnakeCase,
+    /// Keep names as in the source but cut them to the identifier length limit
+    TruncateWithSuffix,
+}
+
+/// Strip the accent from common Latin letters
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'ł' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'þ' => "th",
+        _ => return None,
+    })
+}
+
+/// Cut `name` to at most `max_len` bytes on a character boundary
+fn truncate_identifier(name: &str, max_len: usize) -> &str {
+    let mut end = name.len().min(max_len);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
+
+impl ColumnNaming {
+    /// Column name for a single source field, before de-duplication
+    fn apply(self, name: &str) -> String {
+        match self {
+            ColumnNaming::Preserve => name.to_string(),
+            ColumnNaming::TruncateWithSuffix => {
+                truncate_identifier(name, MAX_IDENTIFIER_LENGTH).to_string()
+            }
+            ColumnNaming::SnakeCase => {
+                let mut snake = String::with_capacity(name.len());
+                let mut previous_lower = false;
+                for c in name.trim().chars() {
+                    let lower = c.to_lowercase().next().unwrap_or(c);
+                    if c.is_ascii_alphanumeric() {
+                        // Split camelCase words
+                        if c.is_ascii_uppercase() && previous_lower {
+                            snake.push('_');
+                        }
+                        snake.push(lower);
+                        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+                    } else if let Some(ascii) = transliterate(lower) {
+                        snake.push_str(ascii);
+                        previous_lower = true;
+                    } else {
+                        if !snake.ends_with('_') {
+                            snake.push('_');
+                        }
+                        previous_lower = false;
+                    }
+                }
+
+                let mut snake = snake.trim_matches('_').to_string();
+                if snake.is_empty() {
+                    snake.push_str("field");
+                } else if snake.starts_with(|c: char| c.is_ascii_digit()) {
+                    snake.insert(0, '_');
+                }
+                truncate_identifier(&snake, MAX_IDENTIFIER_LENGTH).to_string()
+            }
+        }
+    }
+
+    /// Column names for a list of source field names, in order. Names that would collide
+    /// with each other or with the generated `id` column get a numeric suffix, shortening the
+    /// name so the result stays within the identifier limit.
+    pub fn column_names(self, names: &[String]) -> Vec<String> {
+        let mut taken: HashSet<String> = GENERATED_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+
+        names
+            .iter()
+            .map(|name| {
+                let column = unique_column_name(&self.apply(name), &taken);
+                taken.insert(column.clone());
+                column
+            })
+            .collect()
+    }
+}
+
+/// How a layer holding more than one geometry type (e.g. Polygon and MultiPolygon, common in
+/// shapefiles) is stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeometryPromotion {
+    /// Promote single geometries to their multi type, falling back to a generic column when the
+    /// types do not share one (e.g. points and polygons)
+    #[default]
+    ForceMulti,
+    /// Store everything in an unconstrained `GEOMETRY` column
+    Generic,
+    /// Produce one layer per geometry family, each promoted to multi if needed
+    SplitByType,
+}
+
+/// How curved geometries (CircularString, CompoundCurve, CurvePolygon and their multi types,
+/// common in CAD-origin data) are stored
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CurveHandling {
+    /// Keep curve types in the geometry column
+    Preserve,
+    /// Approximate curves with straight segments, at most `max_angle_degrees` of arc each
+    Linearize { max_angle_degrees: f64 },
+}
+
+impl Default for CurveHandling {
+    fn default() -> Self {
+        // GDAL's own default step when linearizing
+        CurveHandling::Linearize {
+            max_angle_degrees: 4.0,
+        }
+    }
+}
+
+/// Provenance written to the database as table and column comments when a layer is created
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaComments {
+    /// Layer description, the first line of the table comment
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Name of the uploaded file
+    #[serde(default)]
+    pub source_file: Option<String>,
+    /// Units keyed by source field name, e.g. `"height" => "m"`
+    #[serde(default)]
+    pub units: HashMap<String, String>,
+}
+
+/// Options applied while extracting layer schemas at ingest time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaOptions {
+    #[serde(default)]
+    pub column_naming: ColumnNaming,
+    #[serde(default)]
+    pub geometry_promotion: GeometryPromotion,
+    #[serde(default)]
+    pub curves: CurveHandling,
+    /// Map strings to TEXT and decimals to DOUBLE PRECISION regardless of the width and
+    /// precision declared by the source
+    #[serde(default)]
+    pub ignore_field_sizes: bool,
+    /// Suffix column names that are reserved words of the target connector (`order` becomes
+    /// `order_`) so hand-written SQL needs no quoting; generated SQL quotes every identifier
+    #[serde(default)]
+    pub rename_reserved_words: bool,
+    /// Name of the geometry column, [`DEFAULT_GEOMETRY_COLUMN`] when not set. A source field
+    /// of the same name keeps it and the geometry column gets a numeric suffix instead.
+    #[serde(default)]
+    pub geometry_column: Option<String>,
+    /// Source field holding stable integer ids to use as the primary key `id` column instead
+    /// of a generated serial. Every feature must have a distinct value.
+    #[serde(default)]
+    pub primary_key: Option<String>,
+    /// SRID assumed when the source declares no CRS, e.g. coordinate columns in a CSV
+    #[serde(default)]
+    pub default_srid: Option<i32>,
+    /// Mark fields that are never empty in the data NOT NULL, whatever the source declares.
+    /// Every feature is scanned, so the constraint holds for the whole layer.
+    #[serde(default)]
+    pub infer_not_null: bool,
+    /// Mark fields whose values never repeat in the data UNIQUE. Fields with more than 100,000
+    /// values are left as they are.
+    #[serde(default)]
+    pub infer_unique: bool,
+    /// Record where the data came from in table and column comments
+    #[serde(default)]
+    pub comments: Option<SchemaComments>,
+}
+
+/// OGR geometry type with the Z/M flags removed, e.g. 3 (Polygon) for PolygonZM
+fn flatten_geometry_type(geometry_type: u32) -> u32 {
+    (geometry_type & !0x8000_0000) % 1000
+}
+
+/// Whether an OGR geometry type carries Z coordinates, in either the ISO (1000/3000) or the
+/// legacy 2.5D (high bit) encoding
+fn geometry_type_has_z(geometry_type: u32) -> bool {
+    geometry_type & 0x8000_0000 != 0 || matches!((geometry_type & !0x8000_0000) / 1000, 1 | 3)
+}
+
+/// Whether an OGR geometry type carries M values (ISO 2000/3000 types)
+fn geometry_type_has_m(geometry_type: u32) -> bool {
+    matches!((geometry_type & !0x8000_0000) / 1000, 2 | 3)
+}
+
+/// Linear type a flattened OGR curve type is approximated by; other types map to themselves
+fn linear_geometry_type(geometry_type: u32) -> u32 {
+    match geometry_type {
+        8 | 9 => 2,
+        10 => 3,
+        11 => 5,
+        12 => 6,
+        other => other,
+    }
+}
+
+/// Multi type a flattened OGR geometry type is promoted to; multi types map to themselves
+fn multi_geometry_type(geometry_type: u32) -> u32 {
+    match geometry_type {
+        1 => 4,
+        2 => 5,
+        3 => 6,
+        8 | 9 => 11,
+        10 => 12,
+        other => other,
+    }
+}
+
+/// PostGIS type name for a flattened OGR geometry type, `GEOMETRY` when unknown
+pub fn postgis_geometry_type(geometry_type: u32) -> &'static str {
+    match flatten_geometry_type(geometry_type) {
+        1 => "POINT",
+        2 => "LINESTRING",
+        3 => "POLYGON",
+        4 => "MULTIPOINT",
+        5 => "MULTILINESTRING",
+        6 => "MULTIPOLYGON",
+        7 => "GEOMETRYCOLLECTION",
+        8 => "CIRCULARSTRING",
+        9 => "COMPOUNDCURVE",
+        10 => "CURVEPOLYGON",
+        11 => "MULTICURVE",
+        12 => "MULTISURFACE",
+        15 => "POLYHEDRALSURFACE",
+        16 => "TIN",
+        17 => "TRIANGLE",
+        _ => "GEOMETRY",
+    }
+}
+
+/// Geometry column type and source types for each output layer. `counts` holds the number of
+/// features per flattened source type; `declared` is the type reported by the layer.
+fn plan_geometry_columns(
+    declared: u32,
+    counts: &BTreeMap<u32, i64>,
+    promotion: GeometryPromotion,
+) -> Vec<(u32, Vec<u32>)> {
+    let types: Vec<u32> = counts.keys().copied().collect();
+    match types.as_slice() {
+        [] => return vec![(flatten_geometry_type(declared), Vec::new())],
+        [single] => return vec![(*single, types.clone())],
+        _ => {}
+    }
+
+    match promotion {
+        GeometryPromotion::Generic => vec![(0, types)],
+        GeometryPromotion::ForceMulti => {
+            let multi = multi_geometry_type(types[0]);
+            if types.iter().all(|t| multi_geometry_type(*t) == multi) {
+                vec![(multi, types)]
+            } else {
+                vec![(0, types)]
+            }
+        }
+        GeometryPromotion::SplitByType => {
+            let mut families: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+            for t in types {
+                families.entry(multi_geometry_type(t)).or_default().push(t);
+            }
+            families
+                .into_iter()
+                .map(|(multi, members)| match members.as_slice() {
+                    [single] => (*single, members),
+                    _ => (multi, members),
+                })
+                .collect()
+        }
+    }
+}
+
 /// Represents a field definition from a GDAL layer
 /// Raw field definition before connector-specific type mapping
 #[derive(Debug, Clone)]
@@ -12,115 +344,1189 @@ struct RawFieldDefinition {
     pub width: Option<i32>,
     pub precision: Option<i32>,
     pub is_nullable: bool,
+    pub is_unique: bool,
+    pub default_value: Option<String>,
+}
+
+/// Running check of whether every value of a String field has a more specific type
+#[derive(Debug, Clone)]
+struct TextFieldSample {
+    index: usize,
+    seen: bool,
+    uuid: bool,
+    json: bool,
+}
+
+impl TextFieldSample {
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            seen: false,
+            uuid: true,
+            json: true,
+        }
+    }
+
+    fn observe(&mut self, value: &str) {
+        self.seen = true;
+        if self.uuid {
+            self.uuid = uuid::Uuid::try_parse(value).is_ok();
+        }
+        // Only objects and arrays: bare numbers and quoted strings are ordinary text
+        if self.json {
+            self.json = matches!(
+                serde_json::from_str::<serde_json::Value>(value),
+                Ok(serde_json::Value::Object(_) | serde_json::Value::Array(_))
+            );
+        }
+    }
+
+    /// Type name to use instead of `String`, when every non-null value agreed on one
+    fn detected_type(&self) -> Option<&'static str> {
+        match (self.seen, self.uuid, self.json) {
+            (true, true, _) => Some("UUID"),
+            (true, _, true) => Some("JSON"),
+            _ => None,
+        }
+    }
+}
+
+/// Most distinct values of a field tracked for UNIQUE inference; fields with more are not
+/// inferred unique, which bounds memory on large uploads
+const MAX_TRACKED_DISTINCT_VALUES: usize = 100_000;
+
+/// Running check of which constraints the values of a field satisfy
+#[derive(Debug, Clone, Default)]
+struct ConstraintSample {
+    seen: bool,
+    null_seen: bool,
+    /// Values so far, dropped at the first repeat or past [`MAX_TRACKED_DISTINCT_VALUES`]
+    distinct: Option<HashSet<String>>,
+}
+
+impl ConstraintSample {
+    fn new(track_unique: bool) -> Self {
+        Self {
+            distinct: track_unique.then(HashSet::new),
+            ..Default::default()
+        }
+    }
+
+    fn observe(&mut self, value: Option<String>) {
+        self.seen = true;
+        match value {
+            Some(value) => {
+                if let Some(distinct) = &mut self.distinct
+                    && (!distinct.insert(value) || distinct.len() > MAX_TRACKED_DISTINCT_VALUES)
+                {
+                    self.distinct = None;
+                }
+            }
+            None => self.null_seen = true,
+        }
+    }
+
+    fn not_null(&self) -> bool {
+        self.seen && !self.null_seen
+    }
+
+    fn unique(&self) -> bool {
+        self.seen && self.distinct.is_some()
+    }
+}
+
+/// Name of the type of field `index` passed to [`VectorConnector::map_gdal_field_type`], e.g.
+/// `Integer64` or `StringList`. Boolean and Int16 subtypes of Integer, and the UUID subtype of
+/// String, are reported as types of their own: `Boolean`, `Int16`, `UUID` and `JSON`.
+pub fn gdal_field_type_name(defn: &Defn, index: usize) -> &'static str {
+    let (field_type, subtype) = unsafe {
+        let field_defn = gdal_sys::OGR_FD_GetFieldDefn(defn.c_defn(), index as i32);
+        (
+            gdal_sys::OGR_Fld_GetType(field_defn),
+            gdal_sys::OGR_Fld_GetSubType(field_defn),
+        )
+    };
+
+    match (field_type, subtype) {
+        (OGRFieldType::OFTInteger, OGRFieldSubType::OFSTBoolean) => "Boolean",
+        (OGRFieldType::OFTInteger, OGRFieldSubType::OFSTInt16) => "Int16",
+        (OGRFieldType::OFTInteger, _) => "Integer",
+        (OGRFieldType::OFTString, OGRFieldSubType::OFSTUUID) => "UUID",
+        (OGRFieldType::OFTString, OGRFieldSubType::OFSTJSON) => "JSON",
+        (OGRFieldType::OFTIntegerList, _) => "IntegerList",
+        (OGRFieldType::OFTReal, _) => "Real",
+        (OGRFieldType::OFTRealList, _) => "RealList",
+        (OGRFieldType::OFTString | OGRFieldType::OFTWideString, _) => "String",
+        (OGRFieldType::OFTStringList | OGRFieldType::OFTWideStringList, _) => "StringList",
+        (OGRFieldType::OFTBinary, _) => "Binary",
+        (OGRFieldType::OFTDate, _) => "Date",
+        (OGRFieldType::OFTTime, _) => "Time",
+        (OGRFieldType::OFTDateTime, _) => "DateTime",
+        (OGRFieldType::OFTInteger64, _) => "Integer64",
+        (OGRFieldType::OFTInteger64List, _) => "Integer64List",
+        _ => "String",
+    }
 }
 
 /// Raw schema extracted from GDAL before type mapping
 #[derive(Debug, Clone)]
 struct RawLayerSchema {
     pub layer_name: String,
-    pub geometry_type: String,
+    /// Geometry type declared by the layer
+    pub geometry_type: u32,
+    /// Features per flattened geometry type actually present
+    pub geometry_type_counts: BTreeMap<u32, i64>,
+    /// Dimensions of the declared type and every feature combined
+    pub dimension: CoordinateDimension,
     pub srid: Option<i32>,
+    pub crs: Option<CrsDefinition>,
     pub fields: Vec<RawFieldDefinition>,
     pub feature_count: i64,
 }
 
 /// Represents a field definition from a GDAL layer
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldDefinition {
+    /// Column name in the generated table
     pub name: String,
+    /// Name of the field in the source file when sanitisation changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_name: Option<String>,
     pub field_type: String, // PostgreSQL type string
     pub width: Option<i32>,
     pub precision: Option<i32>,
     pub is_nullable: bool,
+    #[serde(default)]
+    pub is_unique: bool,
+    /// Column default, e.g. `'unknown'` or `CURRENT_TIMESTAMP`. Only the literals accepted by
+    /// [`default_literal`] are allowed; anything else is rejected when the table is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+    /// Rule every row must satisfy, emitted as a CHECK constraint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<FieldCheck>,
+    /// Column comment, e.g. the original field name and units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Constraint on the values of a field, rendered as a CHECK constraint from literals only
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldCheck {
+    /// Value lies within inclusive bounds; a missing bound is open
+    Range {
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// Value is one of a fixed set, e.g. a coded value domain
+    OneOf { values: Vec<crate::FilterValue> },
+}
+
+/// Normalise a column default to a literal that is safe to paste into DDL: a number, a
+/// single-quoted string (re-escaped), or one of `CURRENT_TIMESTAMP`, `CURRENT_DATE`, `TRUE`,
+/// `FALSE` and `NULL`. Returns `None` for anything else, including arbitrary expressions.
+pub fn default_literal(value: &str) -> Option<String> {
+    let value = value.trim();
+    let keyword = value.to_ascii_uppercase();
+    if matches!(
+        keyword.as_str(),
+        "CURRENT_TIMESTAMP" | "CURRENT_DATE" | "TRUE" | "FALSE" | "NULL"
+    ) {
+        return Some(keyword);
+    }
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'))
+        && value.parse::<f64>().is_ok_and(f64::is_finite)
+    {
+        return Some(value.to_string());
+    }
+    let inner = value.strip_prefix('\'')?.strip_suffix('\'')?;
+    if inner.contains('\0') {
+        return None;
+    }
+    Some(format!(
+        "'{}'",
+        inner.replace("''", "'").replace('\'', "''")
+    ))
 }
 
 /// Represents the complete schema of a GDAL layer
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayerSchema {
     pub layer_name: String,
+    /// Name of the geometry column in the generated table
+    #[serde(default = "default_geometry_column")]
+    pub geometry_column: String,
+    /// PostGIS geometry type of the column, e.g. `MULTIPOLYGON`, or `GEOMETRY` for mixed types
     pub geometry_type: String,
+    /// Source geometry types stored in this layer; empty when every type is accepted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_geometry_types: Vec<String>,
+    /// Whether geometries carry Z and/or M coordinates, kept in the column type
+    #[serde(default)]
+    pub dimension: CoordinateDimension,
+    /// Maximum arc angle in degrees per segment when curves are linearized on insert, `None`
+    /// when curve types are stored as they are
+    #[serde(default)]
+    pub linearize_curves: Option<f64>,
+    /// Source field stored as the `id` column, `None` when ids are generated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<String>,
     pub srid: Option<i32>,
+    /// Source CRS when it has no EPSG code, see [`crate::ConnectorBase::register_crs`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crs: Option<CrsDefinition>,
+    /// Table comment, e.g. the layer description, source file and ingest date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
     pub fields: Vec<FieldDefinition>,
     pub feature_count: i64,
 }
 
-/// Extract schema information from a geospatial file
-pub async fn extract_layer_schema(
+fn default_geometry_column() -> String {
+    DEFAULT_GEOMETRY_COLUMN.to_string()
+}
+
+impl FieldDefinition {
+    /// Name of the field in the source file, for mapping columns back to the original data
+    pub fn source_name(&self) -> &str {
+        self.source_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl LayerSchema {
+    /// Whether a source geometry of OGR type `geometry_type`, as read from the file, belongs in
+    /// this layer. With [`GeometryPromotion::SplitByType`] each feature is routed to the one
+    /// schema that accepts it. Curves are matched as the linear type they become when this
+    /// layer linearizes them.
+    pub fn accepts_geometry_type(&self, geometry_type: u32) -> bool {
+        let mut geometry_type = flatten_geometry_type(geometry_type);
+        if self.linearize_curves.is_some() {
+            geometry_type = linear_geometry_type(geometry_type);
+        }
+        self.source_geometry_types.is_empty()
+            || self
+                .source_geometry_types
+                .iter()
+                .any(|name| name == postgis_geometry_type(geometry_type))
+    }
+
+    /// Source of the table created from this schema in `namespace`, to be recorded in layer
+    /// metadata so tiles and queries read the geometry column that was actually created
+    pub fn layer_source(&self, namespace: &str) -> crate::LayerSource {
+        crate::LayerSource::Database {
+            namespace: namespace.to_string(),
+            name: self.layer_name.clone(),
+            geometry_field: self.geometry_column.clone(),
+            // Tables are created in WGS84 when the source declares no SRID
+            srid: self
+                .srid
+                .and_then(|srid| crate::Srid::try_from(srid).ok())
+                .unwrap_or(crate::Srid::EPSG4326),
+        }
+    }
+
+    /// Whether single geometries must be converted with `ST_Multi` on insert
+    pub fn is_multi(&self) -> bool {
+        self.geometry_type.starts_with("MULTI")
+    }
+
+    /// Field read from the source field `source_name`
+    pub fn field_by_source_name(&self, source_name: &str) -> Option<&FieldDefinition> {
+        self.fields
+            .iter()
+            .find(|field| field.source_name() == source_name)
+    }
+}
+
+/// Summary of one layer in a dataset, for choosing which layers to import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerInfo {
+    pub name: String,
+    /// Declared PostGIS geometry type, `GEOMETRY` when the layer does not declare one
+    pub geometry_type: String,
+    pub feature_count: u64,
+    pub srid: Option<i32>,
+}
+
+/// List the layers of a dataset, e.g. the tables of a GeoPackage or FileGDB, without
+/// scanning their features
+pub fn list_layers(dataset: &Dataset) -> Vec<LayerInfo> {
+    dataset
+        .layers()
+        .map(|layer| LayerInfo {
+            name: layer.name(),
+            geometry_type: postgis_geometry_type(layer.defn().geometry_type()).to_string(),
+            feature_count: layer.feature_count(),
+            srid: layer.spatial_ref().and_then(|srs| srs.auth_code().ok()),
+        })
+        .collect()
+}
+
+/// Extract schema information from the first layer of a geospatial file. Features are scanned
+/// for the geometry types actually present, so one schema is returned per output layer: a
+/// single schema unless `options` asks for [`GeometryPromotion::SplitByType`] on a mixed layer.
+pub async fn extract_layer_schemas(
     dataset: Dataset,
     connector: &dyn VectorConnector,
-) -> Result<LayerSchema, Box<dyn std::error::Error + Send + Sync>> {
+    options: &SchemaOptions,
+) -> Result<Vec<LayerSchema>, Box<dyn std::error::Error + Send + Sync>> {
+    extract_selected_layer_schemas(dataset, LayerSelector::Index(0), connector, options).await
+}
+
+/// As [`extract_layer_schemas`], for the layer of a multi-layer file chosen by `selector`
+pub async fn extract_selected_layer_schemas(
+    dataset: Dataset,
+    selector: LayerSelector,
+    connector: &dyn VectorConnector,
+    options: &SchemaOptions,
+) -> Result<Vec<LayerSchema>, Box<dyn std::error::Error + Send + Sync>> {
+    let scan_options = options.clone();
+
     // TODO: Run file processing in a queue
     // Run GDAL operations in a blocking task since GDAL is not async
     let raw_schema = task::spawn_blocking(move || {
-        // Get the first layer (GeoJSON typically has one layer)
-        let layer = dataset.layer(0)?;
+        let mut layer = selector.get_layer(&dataset).map_err(|e| e.to_string())?;
+        read_raw_layer_schema(&mut layer, &scan_options)
+    })
+    .await??;
+
+    Ok(map_raw_layer_schema(raw_schema, connector, options))
+}
 
-        // Extract basic layer information
+/// As [`extract_layer_schemas`], for every layer of a multi-layer file such as a GeoPackage
+pub async fn extract_all_layer_schemas(
+    dataset: Dataset,
+    connector: &dyn VectorConnector,
+    options: &SchemaOptions,
+) -> Result<Vec<LayerSchema>, Box<dyn std::error::Error + Send + Sync>> {
+    let scan_options = options.clone();
+
+    let raw_schemas = task::spawn_blocking(move || {
+        dataset
+            .layers()
+            .map(|mut layer| read_raw_layer_schema(&mut layer, &scan_options))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await??;
+
+    Ok(raw_schemas
+        .into_iter()
+        .flat_map(|raw_schema| map_raw_layer_schema(raw_schema, connector, options))
+        .collect())
+}
+
+/// Up to `limit` features of the layer chosen by `selector` as a GeoJSON FeatureCollection in
+/// WGS 84, for showing an upload on a map before the user confirms the import. Features whose
+/// geometry cannot be reprojected are returned without one.
+pub async fn preview_features(
+    dataset: Dataset,
+    selector: LayerSelector,
+    limit: usize,
+) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+    task::spawn_blocking(move || {
+        let mut layer = selector.get_layer(&dataset).map_err(|e| e.to_string())?;
+
+        let mut wgs84 = SpatialRef::from_epsg(4326)?;
+        wgs84.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+        // Layers without a CRS are assumed to be WGS 84 already, as on ingest
+        let transform = match layer.spatial_ref() {
+            Some(mut source) => {
+                source.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+                Some(CoordTransform::new(&source, &wgs84)?)
+            }
+            None => None,
+        };
+
+        let mut features = Vec::new();
+        for feature in layer.features().take(limit) {
+            let geometry = feature.geometry().and_then(|geometry| {
+                let json = match &transform {
+                    Some(transform) => geometry.transform(transform).ok()?.json(),
+                    None => geometry.json(),
+                };
+                serde_json::from_str::<serde_json::Value>(&json.ok()?).ok()
+            });
+            let properties: serde_json::Map<String, serde_json::Value> = feature
+                .fields()
+                .map(|(name, value)| (name, field_value_json(value)))
+                .collect();
+            features.push(serde_json::json!({
+                "type": "Feature",
+                "id": feature.fid(),
+                "geometry": geometry,
+                "properties": properties,
+            }));
+        }
+
+        Ok(serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features,
+        }))
+    })
+    .await?
+}
+
+/// Which features [`sample_field_stats`] reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SampleMethod {
+    /// The first `count` features, cheapest but biased for sorted files
+    First { count: usize },
+    /// `count` features spread at random over the layer; the same `seed` picks the same ones
+    Random { count: usize, seed: u64 },
+}
+
+/// Statistics of one field over the sampled features
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampledFieldStats {
+    pub field: String,
+    /// GDAL type name, e.g. `Integer64` or `String`
+    pub field_type: String,
+    pub null_count: u64,
+    /// Share of sampled features where the field is null, 0 to 1
+    pub null_rate: f64,
+    /// Distinct non-null values in the sample, a lower bound for the whole layer
+    pub distinct_count: u64,
+    /// Smallest value: numbers compare numerically, anything else as text
+    pub min: Option<serde_json::Value>,
+    pub max: Option<serde_json::Value>,
+}
+
+/// Result of [`sample_field_stats`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleStats {
+    pub layer_name: String,
+    pub feature_count: u64,
+    pub sampled: u64,
+    pub fields: Vec<SampledFieldStats>,
+}
+
+/// Order of two property values for min/max, `None` when they are not comparable
+fn compare_json_values(a: &serde_json::Value, b: &serde_json::Value) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => {
+            a.as_f64()?.partial_cmp(&b.as_f64()?)
+        }
+        (serde_json::Value::String(a), serde_json::Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Indexes of `count` distinct features out of `total`, chosen with Floyd's algorithm from a
+/// xorshift generator so no random number crate is needed
+fn random_sample_indexes(total: u64, count: u64, seed: u64) -> HashSet<u64> {
+    let mut state = seed | 1;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut chosen = HashSet::new();
+    for upper in total.saturating_sub(count)..total {
+        let candidate = next() % (upper + 1);
+        if !chosen.insert(candidate) {
+            chosen.insert(upper);
+        }
+    }
+    chosen
+}
+
+/// Sample features of the layer chosen by `selector` and report null rates, distinct counts and
+/// value ranges per field, to review an upload and check inferred types before ingest
+pub async fn sample_field_stats(
+    dataset: Dataset,
+    selector: LayerSelector,
+    method: SampleMethod,
+) -> Result<SampleStats, Box<dyn std::error::Error + Send + Sync>> {
+    task::spawn_blocking(move || {
+        let mut layer = selector.get_layer(&dataset).map_err(|e| e.to_string())?;
         let layer_name = layer.name();
         let feature_count = layer.feature_count();
+        let defn = layer.defn();
+        let field_types: Vec<String> = (0..defn.fields().count())
+            .map(|index| gdal_field_type_name(&defn, index).to_string())
+            .collect();
+        let field_names: Vec<String> = defn.fields().map(|field| field.name()).collect();
 
-        // Extract spatial reference system and SRID
-        let srid = if let Some(srs) = layer.spatial_ref() {
-            srs.auth_code().ok()
-        } else {
-            None
+        let (count, chosen) = match method {
+            SampleMethod::First { count } => (count as u64, None),
+            SampleMethod::Random { count, seed } => (
+                count as u64,
+                Some(random_sample_indexes(feature_count, count as u64, seed)),
+            ),
         };
 
-        // Get layer definition to extract field information
-        let layer_defn = layer.defn();
-
-        // Extract field definitions
-        let mut raw_fields = Vec::new();
-        for field_defn in layer_defn.fields() {
-            let raw_field = RawFieldDefinition {
-                name: field_defn.name(),
-                gdal_field_type: format!("{:?}", field_defn.field_type()),
-                width: if field_defn.width() > 0 {
-                    Some(field_defn.width())
-                } else {
-                    None
-                },
-                precision: if field_defn.precision() > 0 {
-                    Some(field_defn.precision())
-                } else {
-                    None
-                },
-                is_nullable: field_defn.is_nullable(),
-            };
+        let mut null_counts = vec![0u64; field_names.len()];
+        let mut distinct: Vec<HashSet<String>> = vec![HashSet::new(); field_names.len()];
+        let mut ranges: Vec<Option<(serde_json::Value, serde_json::Value)>> =
+            vec![None; field_names.len()];
+        let mut sampled = 0u64;
+
+        for (index, feature) in layer.features().enumerate() {
+            if sampled >= count {
+                break;
+            }
+            if let Some(chosen) = &chosen
+                && !chosen.contains(&(index as u64))
+            {
+                continue;
+            }
+            sampled += 1;
 
-            raw_fields.push(raw_field);
+            for (field_index, (_, value)) in feature.fields().enumerate() {
+                let value = field_value_json(value);
+                if value.is_null() {
+                    null_counts[field_index] += 1;
+                    continue;
+                }
+                distinct[field_index].insert(value.to_string());
+                ranges[field_index] = Some(match ranges[field_index].take() {
+                    None => (value.clone(), value),
+                    Some((min, max)) => {
+                        let min = match compare_json_values(&value, &min) {
+                            Some(std::cmp::Ordering::Less) => value.clone(),
+                            _ => min,
+                        };
+                        let max = match compare_json_values(&value, &max) {
+                            Some(std::cmp::Ordering::Greater) => value,
+                            _ => max,
+                        };
+                        (min, max)
+                    }
+                });
+            }
         }
 
-        let geometry_type_name = gdal::vector::geometry_type_to_name(layer_defn.geometry_type());
+        let fields = field_names
+            .into_iter()
+            .zip(field_types)
+            .enumerate()
+            .map(|(index, (field, field_type))| {
+                let (min, max) = ranges[index].take().unzip();
+                SampledFieldStats {
+                    field,
+                    field_type,
+                    null_count: null_counts[index],
+                    null_rate: if sampled == 0 {
+                        0.0
+                    } else {
+                        null_counts[index] as f64 / sampled as f64
+                    },
+                    distinct_count: distinct[index].len() as u64,
+                    min,
+                    max,
+                }
+            })
+            .collect();
 
-        Ok::<RawLayerSchema, Box<dyn std::error::Error + Send + Sync>>(RawLayerSchema {
+        Ok(SampleStats {
             layer_name,
-            geometry_type: geometry_type_name,
-            srid,
-            fields: raw_fields,
-            feature_count: feature_count.try_into().unwrap(),
+            feature_count,
+            sampled,
+            fields,
         })
     })
-    .await??;
+    .await?
+}
+
+/// GeoJSON property value of a GDAL field
+fn field_value_json(value: Option<gdal::vector::FieldValue>) -> serde_json::Value {
+    use gdal::vector::FieldValue;
+    match value {
+        Some(FieldValue::StringValue(s)) => s.into(),
+        Some(FieldValue::IntegerValue(i)) => i.into(),
+        Some(FieldValue::Integer64Value(i)) => i.into(),
+        Some(FieldValue::RealValue(f)) => f.into(),
+        Some(FieldValue::DateValue(date)) => date.format("%Y-%m-%d").to_string().into(),
+        Some(FieldValue::DateTimeValue(datetime)) => datetime.to_rfc3339().into(),
+        Some(FieldValue::IntegerListValue(list)) => list.into(),
+        Some(FieldValue::Integer64ListValue(list)) => list.into(),
+        Some(FieldValue::StringListValue(list)) => list.into(),
+        Some(FieldValue::RealListValue(list)) => list.into(),
+        None => serde_json::Value::Null,
+    }
+}
+
+/// EPSG code of a spatial reference, or its full definition when it has none. Files often
+/// omit the authority (e.g. shapefile .prj), so GDAL is asked to identify one before falling
+/// back to the definition.
+fn identify_crs(srs: Option<SpatialRef>) -> (Option<i32>, Option<CrsDefinition>) {
+    match srs {
+        Some(mut srs) => match srs.auth_code() {
+            Ok(code) => (Some(code), None),
+            Err(_) => match srs.auto_identify_epsg().and_then(|_| srs.auth_code()) {
+                Ok(code) => (Some(code), None),
+                Err(_) => (None, srs.to_wkt().ok().map(CrsDefinition::Wkt)),
+            },
+        },
+        None => (None, None),
+    }
+}
+
+/// Read a layer's fields, CRS and geometry types, scanning every feature
+fn read_raw_layer_schema(
+    layer: &mut Layer,
+    options: &SchemaOptions,
+) -> Result<RawLayerSchema, Box<dyn std::error::Error + Send + Sync>> {
+    // Extract basic layer information
+    let layer_name = layer.name();
+    let feature_count = layer.feature_count();
+
+    // Extract spatial reference system and SRID
+    let (srid, crs) = identify_crs(layer.spatial_ref());
+
+    // Get layer definition to extract field information
+    let layer_defn = layer.defn();
+
+    // Extract field definitions
+    let mut raw_fields = Vec::new();
+    for (index, field_defn) in layer_defn.fields().enumerate() {
+        let raw_field = RawFieldDefinition {
+            name: field_defn.name(),
+            gdal_field_type: gdal_field_type_name(&layer_defn, index).to_string(),
+            width: if field_defn.width() > 0 {
+                Some(field_defn.width())
+            } else {
+                None
+            },
+            precision: if field_defn.precision() > 0 {
+                Some(field_defn.precision())
+            } else {
+                None
+            },
+            is_nullable: field_defn.is_nullable(),
+            is_unique: field_defn.is_unique(),
+            default_value: field_defn.default_value().and_then(|value| {
+                let literal = default_literal(&value);
+                if literal.is_none() {
+                    tracing::warn!(
+                        "Ignoring unsupported default {:?} of field '{}'",
+                        value,
+                        field_defn.name()
+                    );
+                }
+                literal
+            }),
+        };
+
+        raw_fields.push(raw_field);
+    }
+
+    let primary_key_index = match &options.primary_key {
+        Some(primary_key) => {
+            let index = raw_fields
+                .iter()
+                .position(|field| &field.name == primary_key)
+                .ok_or_else(|| format!("Primary key field '{}' does not exist", primary_key))?;
+            if !matches!(
+                raw_fields[index].gdal_field_type.as_str(),
+                "Integer" | "Integer64" | "Int16"
+            ) {
+                return Err(format!(
+                    "Primary key field '{}' must be an integer field, not {}",
+                    primary_key, raw_fields[index].gdal_field_type
+                )
+                .into());
+            }
+            Some(index)
+        }
+        None => None,
+    };
+    let mut primary_key_values = HashSet::new();
 
+    let geometry_type = layer_defn.geometry_type();
+
+    // Declared types are often wrong (shapefiles report Polygon for MultiPolygon data), so
+    // the types present are counted from the features themselves
+    let mut geometry_type_counts = BTreeMap::new();
+    let mut has_z = geometry_type_has_z(geometry_type);
+    let mut has_m = geometry_type_has_m(geometry_type);
+    // String fields are checked for values that all share a stricter type, e.g. UUIDs or
+    // nested JSON written out as text
+    let mut text_samples: Vec<TextFieldSample> = raw_fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.gdal_field_type == "String")
+        .map(|(index, _)| TextFieldSample::new(index))
+        .collect();
+    // Constraint inference needs every field's values, not just the ones GDAL flags
+    let mut constraint_samples: Vec<ConstraintSample> =
+        if options.infer_not_null || options.infer_unique {
+            raw_fields
+                .iter()
+                .map(|_| ConstraintSample::new(options.infer_unique))
+                .collect()
+        } else {
+            Vec::new()
+        };
+    for feature in layer.features() {
+        for (index, sample) in constraint_samples.iter_mut().enumerate() {
+            sample.observe(feature.field_as_string(index as i32).ok().flatten());
+        }
+
+        if let Some(index) = primary_key_index {
+            let field_name = &raw_fields[index].name;
+            let value = feature.field_as_integer64(index as i32)?.ok_or_else(|| {
+                format!(
+                    "Primary key field '{}' is empty on feature {}",
+                    field_name,
+                    feature.fid().unwrap_or_default()
+                )
+            })?;
+            if !primary_key_values.insert(value) {
+                return Err(format!(
+                    "Primary key field '{}' has duplicate value {}",
+                    field_name, value
+                )
+                .into());
+            }
+        }
+
+        for sample in &mut text_samples {
+            if let Ok(Some(value)) = feature.field_as_string(sample.index as i32) {
+                sample.observe(&value);
+            }
+        }
+
+        if let Some(geometry) = feature.geometry() {
+            let feature_type = geometry.geometry_type();
+            has_z |= geometry_type_has_z(feature_type);
+            has_m |= geometry_type_has_m(feature_type);
+            let flat = flatten_geometry_type(feature_type);
+            if flat != 0 && flat != 100 {
+                *geometry_type_counts.entry(flat).or_insert(0) += 1;
+            }
+        }
+    }
+
+    for sample in &text_samples {
+        if let Some(detected_type) = sample.detected_type() {
+            raw_fields[sample.index].gdal_field_type = detected_type.to_string();
+        }
+    }
+
+    for (field, sample) in raw_fields.iter_mut().zip(&constraint_samples) {
+        if options.infer_not_null && sample.not_null() {
+            field.is_nullable = false;
+        }
+        if options.infer_unique && sample.unique() {
+            field.is_unique = true;
+        }
+    }
+
+    Ok(RawLayerSchema {
+        layer_name,
+        geometry_type,
+        geometry_type_counts,
+        dimension: CoordinateDimension::new(has_z, has_m),
+        srid,
+        crs,
+        fields: raw_fields,
+        feature_count: feature_count.try_into().unwrap(),
+    })
+}
+
+/// Map a raw schema to connector column types and plan its geometry columns
+fn map_raw_layer_schema(
+    raw_schema: RawLayerSchema,
+    connector: &dyn VectorConnector,
+    options: &SchemaOptions,
+) -> Vec<LayerSchema> {
     // Map field types using connector
+    let source_names: Vec<String> = raw_schema
+        .fields
+        .iter()
+        .map(|field| field.name.clone())
+        .collect();
+    let mut column_names = options.column_naming.column_names(&source_names);
+    if options.rename_reserved_words {
+        let reserved_words = connector.reserved_words();
+        let mut taken: HashSet<String> = column_names.iter().cloned().collect();
+        for column_name in column_names.iter_mut() {
+            if reserved_words.contains(&column_name.to_lowercase().as_str()) {
+                let renamed = unique_column_name(&format!("{}_", column_name), &taken);
+                taken.insert(renamed.clone());
+                *column_name = renamed;
+            }
+        }
+    }
+    if let Some(primary_key) = &options.primary_key
+        && let Some(index) = source_names.iter().position(|name| name == primary_key)
+    {
+        column_names[index] = ID_COLUMN.to_string();
+    }
+    let geometry_column = unique_column_name(
+        options
+            .geometry_column
+            .as_deref()
+            .unwrap_or(DEFAULT_GEOMETRY_COLUMN),
+        &column_names
+            .iter()
+            .cloned()
+            .chain([ID_COLUMN.to_string()])
+            .collect(),
+    );
     let mut mapped_fields = Vec::new();
-    for raw_field in raw_schema.fields {
+    for (raw_field, column_name) in raw_schema.fields.into_iter().zip(column_names) {
+        let comment =
+            options
+                .comments
+                .as_ref()
+                .map(|comments| match comments.units.get(&raw_field.name) {
+                    Some(units) => format!("Source field: {}; units: {}", raw_field.name, units),
+                    None => format!("Source field: {}", raw_field.name),
+                });
         let field_def = FieldDefinition {
-            name: raw_field.name,
-            field_type: connector.map_gdal_field_type(&raw_field.gdal_field_type),
+            source_name: (column_name != raw_field.name).then_some(raw_field.name),
+            name: column_name,
+            field_type: if options.ignore_field_sizes {
+                connector.map_gdal_field_type(&raw_field.gdal_field_type, None, None)
+            } else {
+                connector.map_gdal_field_type(
+                    &raw_field.gdal_field_type,
+                    raw_field.width,
+                    raw_field.precision,
+                )
+            },
             width: raw_field.width,
             precision: raw_field.precision,
             is_nullable: raw_field.is_nullable,
+            is_unique: raw_field.is_unique,
+            default_value: raw_field.default_value,
+            check: None,
+            comment,
         };
         mapped_fields.push(field_def);
     }
 
-    Ok(LayerSchema {
-        layer_name: raw_schema.layer_name,
-        geometry_type: raw_schema.geometry_type,
-        srid: raw_schema.srid,
-        fields: mapped_fields,
-        feature_count: raw_schema.feature_count,
+    let srid = match (raw_schema.srid, &raw_schema.crs) {
+        (None, None) => options.default_srid,
+        (srid, _) => srid,
+    };
+
+    // Linearized curves are planned as the linear types they become
+    let (declared_type, geometry_type_counts, linearize_curves) = match options.curves {
+        CurveHandling::Preserve => (
+            raw_schema.geometry_type,
+            raw_schema.geometry_type_counts,
+            None,
+        ),
+        CurveHandling::Linearize { max_angle_degrees } => {
+            let mut counts = BTreeMap::new();
+            for (geometry_type, count) in raw_schema.geometry_type_counts {
+                *counts
+                    .entry(linear_geometry_type(geometry_type))
+                    .or_insert(0) += count;
+            }
+            (
+                linear_geometry_type(flatten_geometry_type(raw_schema.geometry_type)),
+                counts,
+                Some(max_angle_degrees),
+            )
+        }
+    };
+    let plans = plan_geometry_columns(
+        declared_type,
+        &geometry_type_counts,
+        options.geometry_promotion,
+    );
+    let split = plans.len() > 1;
+
+    let comment = options.comments.as_ref().map(|comments| {
+        let mut lines = Vec::new();
+        if let Some(description) = &comments.description {
+            lines.push(description.clone());
+        }
+        lines.push(match &comments.source_file {
+            Some(source_file) => {
+                format!("Source: {} (layer {})", source_file, raw_schema.layer_name)
+            }
+            None => format!("Source layer: {}", raw_schema.layer_name),
+        });
+        lines.push(format!("Ingested: {}", chrono::Utc::now().to_rfc3339()));
+        lines.join("\n")
+    });
+
+    plans
+        .into_iter()
+        .map(|(column_type, source_types)| {
+            let geometry_type = postgis_geometry_type(column_type).to_string();
+            let (layer_name, feature_count) = if split {
+                (
+                    format!("{}_{}", raw_schema.layer_name, geometry_type.to_lowercase()),
+                    source_types.iter().map(|t| geometry_type_counts[t]).sum(),
+                )
+            } else {
+                (raw_schema.layer_name.clone(), raw_schema.feature_count)
+            };
+
+            LayerSchema {
+                layer_name,
+                geometry_type,
+                source_geometry_types: if split {
+                    source_types
+                        .iter()
+                        .map(|t| postgis_geometry_type(*t).to_string())
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+                dimension: raw_schema.dimension,
+                geometry_column: geometry_column.clone(),
+                linearize_curves,
+                primary_key: options.primary_key.clone(),
+                srid,
+                crs: raw_schema.crs.clone(),
+                comment: comment.clone(),
+                fields: mapped_fields.clone(),
+                feature_count,
+            }
+        })
+        .collect()
+}
+
+/// Extract size, georeference, CRS, band statistics and overviews from a raster file, the
+/// raster counterpart of [`extract_layer_schemas`] for reviewing an upload before ingest
+pub async fn extract_raster_info(
+    dataset: Dataset,
+) -> Result<crate::RasterInfo, Box<dyn std::error::Error + Send + Sync>> {
+    task::spawn_blocking(move || {
+        let (width, height) = dataset.raster_size();
+        if dataset.raster_count() == 0 {
+            return Err("Dataset has no raster bands".into());
+        }
+
+        let geo_transform = dataset.geo_transform().ok();
+        let extent = geo_transform.map(|gt| {
+            // Corners may be rotated, so the extent covers all four
+            let (width, height) = (width as f64, height as f64);
+            let corners =
+                [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)].map(|(px, py)| {
+                    (
+                        gt[0] + px * gt[1] + py * gt[2],
+                        gt[3] + px * gt[4] + py * gt[5],
+                    )
+                });
+            corners.into_iter().fold(
+                [f64::MAX, f64::MAX, f64::MIN, f64::MIN],
+                |[min_x, min_y, max_x, max_y], (x, y)| {
+                    [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                },
+            )
+        });
+
+        let (srid, crs) = identify_crs(dataset.spatial_ref().ok());
+
+        let mut band_info = Vec::new();
+        for (index, band) in dataset.rasterbands().enumerate() {
+            let band = band?;
+            let unit = band.unit();
+            // Approximate statistics come from overviews or a pixel sample, quick on big files
+            let statistics =
+                band.get_statistics(true, true)
+                    .ok()
+                    .flatten()
+                    .map(|stats| crate::BandStatistics {
+                        min: stats.min,
+                        max: stats.max,
+                        mean: stats.mean,
+                        stddev: stats.std_dev,
+                    });
+            band_info.push(crate::RasterBandInfo {
+                index: index as u32 + 1,
+                description: band.description().ok().filter(|d| !d.is_empty()),
+                data_type: band.band_type().name(),
+                no_data_value: band.no_data_value(),
+                color_interpretation: band.color_interpretation().name(),
+                unit: (!unit.is_empty()).then_some(unit),
+                statistics,
+            });
+        }
+
+        let first_band = dataset.rasterband(1)?;
+        let mut overview_levels = Vec::new();
+        for overview_index in 0..first_band.overview_count()?.max(0) as usize {
+            let overview = first_band.overview(overview_index)?;
+            let (overview_width, _) = overview.size();
+            if overview_width > 0 {
+                overview_levels.push((width as f64 / overview_width as f64).round() as u32);
+            }
+        }
+
+        Ok(crate::RasterInfo {
+            width: width as u32,
+            height: height as u32,
+            bands: band_info.len() as u32,
+            data_type: band_info[0].data_type.clone(),
+            no_data_value: band_info[0].no_data_value,
+            geo_transform,
+            extent,
+            srid,
+            crs,
+            band_info,
+            overview_count: overview_levels.len() as u32,
+            overview_levels,
+        })
+    })
+    .await?
+}
+
+/// Build external overviews (a `.ovr` sidecar, or internal overviews for formats such as
+/// GeoTIFF opened for update) at the given decimation factors, e.g. `[2, 4, 8, 16]`, so
+/// low-zoom reads of a large raster file stay fast
+pub async fn build_raster_overviews(
+    mut dataset: Dataset,
+    factors: Vec<u32>,
+    resampling: crate::Resampling,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    task::spawn_blocking(move || {
+        let factors: Vec<i32> = factors.iter().map(|factor| *factor as i32).collect();
+        // An empty band list builds overviews for every band
+        dataset.build_overviews(resampling.gdal_name(), &factors, &[])?;
+        Ok(())
     })
+    .await?
+}
+
+/// Options for [`export_cog`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CogOptions {
+    /// Internal tile width and height in pixels
+    #[serde(default = "default_cog_block_size")]
+    pub block_size: u32,
+    /// GDAL COG compression, e.g. `DEFLATE`, `LZW`, `ZSTD`, `JPEG` or `WEBP`
+    #[serde(default = "default_cog_compression")]
+    pub compression: String,
+    #[serde(default)]
+    pub overview_resampling: crate::Resampling,
+    /// Only write this `[min_x, min_y, max_x, max_y]` window, in the raster's CRS
+    #[serde(default)]
+    pub bbox: Option<[f64; 4]>,
+}
+
+fn default_cog_block_size() -> u32 {
+    512
+}
+
+fn default_cog_compression() -> String {
+    "DEFLATE".to_string()
+}
+
+impl Default for CogOptions {
+    fn default() -> Self {
+        CogOptions {
+            block_size: default_cog_block_size(),
+            compression: default_cog_compression(),
+            overview_resampling: crate::Resampling::default(),
+            bbox: None,
+        }
+    }
+}
+
+/// Write a raster, or a window of it, as a tiled Cloud Optimized GeoTIFF with overviews. The
+/// destination may be a local path or object storage such as S3. Returns the GDAL path written.
+pub async fn export_cog(
+    dataset: Dataset,
+    destination: crate::file_utils::VsiSource,
+    options: CogOptions,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let path = destination.to_vsi_path().map_err(|e| e.to_string())?;
+    task::spawn_blocking(move || {
+        let mut args = gdal::cpl::CslStringList::new();
+        args.add_string("-of")?;
+        args.add_string("COG")?;
+        for option in [
+            format!("BLOCKSIZE={}", options.block_size),
+            format!("COMPRESS={}", options.compression),
+            format!(
+                "OVERVIEW_RESAMPLING={}",
+                options.overview_resampling.gdal_name()
+            ),
+            "OVERVIEWS=IGNORE_EXISTING".to_string(),
+        ] {
+            args.add_string("-co")?;
+            args.add_string(&option)?;
+        }
+        if let Some([min_x, min_y, max_x, max_y]) = options.bbox {
+            args.add_string("-projwin")?;
+            for value in [min_x, max_y, max_x, min_y] {
+                args.add_string(&value.to_string())?;
+            }
+        }
+
+        let c_path = std::ffi::CString::new(path.as_str())?;
+        let translate_options =
+            unsafe { gdal_sys::GDALTranslateOptionsNew(args.as_ptr(), std::ptr::null_mut()) };
+        if translate_options.is_null() {
+            return Err("Invalid COG options".into());
+        }
+        let output = unsafe {
+            gdal_sys::GDALTranslate(
+                c_path.as_ptr(),
+                dataset.c_dataset(),
+                translate_options,
+                std::ptr::null_mut(),
+            )
+        };
+        unsafe { gdal_sys::GDALTranslateOptionsFree(translate_options) };
+        if output.is_null() {
+            return Err(format!("Failed to write COG to {}", path).into());
+        }
+        // Closing flushes the file, which is only complete afterwards
+        unsafe { gdal_sys::GDALClose(output) };
+        Ok(path)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_and_number_defaults() {
+        assert_eq!(
+            default_literal("current_timestamp").as_deref(),
+            Some("CURRENT_TIMESTAMP")
+        );
+        assert_eq!(default_literal("true").as_deref(), Some("TRUE"));
+        assert_eq!(default_literal("Null").as_deref(), Some("NULL"));
+        assert_eq!(default_literal("42").as_deref(), Some("42"));
+        assert_eq!(default_literal("-1.5e3").as_deref(), Some("-1.5e3"));
+        assert_eq!(default_literal("1e999"), None);
+        assert_eq!(default_literal("NaN"), None);
+        assert_eq!(default_literal("e"), None);
+    }
+
+    #[test]
+    fn string_defaults_are_re_escaped() {
+        assert_eq!(default_literal("'road'").as_deref(), Some("'road'"));
+        assert_eq!(default_literal("''").as_deref(), Some("''"));
+        assert_eq!(default_literal("'it''s'").as_deref(), Some("'it''s'"));
+        assert_eq!(default_literal("'it's'").as_deref(), Some("'it''s'"));
+        assert_eq!(
+            default_literal("'x'); DROP TABLE t; --'").as_deref(),
+            Some("'x''); DROP TABLE t; --'")
+        );
+        assert_eq!(default_literal("'a\0b'"), None);
+        assert_eq!(default_literal("'"), None);
+        assert_eq!(default_literal("'unterminated"), None);
+    }
+
+    #[test]
+    fn expressions_are_rejected() {
+        assert_eq!(default_literal("nextval('seq'::regclass)"), None);
+        assert_eq!(default_literal("now()"), None);
+        assert_eq!(default_literal(" now "), None);
+        assert_eq!(default_literal("1 + 1"), None);
+        assert_eq!(default_literal("'a'::text"), None);
+        assert_eq!(default_literal(""), None);
+    }
 }
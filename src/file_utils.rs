@@ -1,9 +1,1416 @@
 // Function to open a geospatial file using GDAL DATASET
-use gdal::Dataset;
+use gdal::vector::LayerAccess;
+use gdal::{Dataset, DatasetOptions, GdalOpenFlags, Metadata};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::path::Path;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task;
 
 pub fn open_dataset<P: AsRef<Path>>(file_path: P) -> Result<Dataset, Box<dyn Error>> {
     let dataset = Dataset::open(file_path)?;
     Ok(dataset)
 }
+
+/// GDAL settings for [`open_dataset_with_options`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenDatasetOptions {
+    /// Driver open options as `KEY=VALUE`, e.g. `X_POSSIBLE_NAMES=lon` or `ENCODING=CP1252`
+    #[serde(default)]
+    pub open_options: Vec<String>,
+    /// Short names of the only drivers allowed to open the file, e.g. `["GPKG", "GeoJSON"]`.
+    /// Untrusted uploads should be restricted to the formats the host expects, so a crafted
+    /// file cannot reach an unrelated driver.
+    #[serde(default)]
+    pub allowed_drivers: Option<Vec<String>>,
+    /// Only open the file as this kind of data, any kind when `None`
+    #[serde(default)]
+    pub kind: Option<DataKind>,
+}
+
+/// Open a geospatial file with driver open options and an optional driver allow-list
+pub fn open_dataset_with_options<P: AsRef<Path>>(
+    file_path: P,
+    options: &OpenDatasetOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let open_options: Vec<&str> = options.open_options.iter().map(String::as_str).collect();
+    let allowed_drivers: Option<Vec<&str>> = options
+        .allowed_drivers
+        .as_ref()
+        .map(|drivers| drivers.iter().map(String::as_str).collect());
+    let open_flags = match options.kind {
+        Some(DataKind::Vector) => GdalOpenFlags::GDAL_OF_VECTOR,
+        Some(DataKind::Raster) => GdalOpenFlags::GDAL_OF_RASTER,
+        Some(DataKind::Both) => GdalOpenFlags::GDAL_OF_VECTOR | GdalOpenFlags::GDAL_OF_RASTER,
+        None => GdalOpenFlags::GDAL_OF_READONLY,
+    };
+    let dataset = Dataset::open_ex(
+        file_path,
+        DatasetOptions {
+            open_flags,
+            open_options: (!open_options.is_empty()).then_some(open_options.as_slice()),
+            allowed_drivers: allowed_drivers.as_deref(),
+            ..Default::default()
+        },
+    )?;
+    Ok(dataset)
+}
+
+/// Open a geospatial file off the async runtime, failing once `timeout` has passed. GDAL cannot
+/// cancel an open, so on timeout the blocking thread runs to completion and its dataset is
+/// dropped.
+pub async fn open_dataset_async<P: AsRef<Path>>(
+    file_path: P,
+    options: &OpenDatasetOptions,
+    timeout: Duration,
+) -> Result<Dataset, Box<dyn Error + Send + Sync>> {
+    let file_path = file_path.as_ref().to_path_buf();
+    let options = options.clone();
+
+    // Network files (/vsicurl/, /vsis3/) can block for seconds inside GDAL
+    let open = task::spawn_blocking(move || {
+        open_dataset_with_options(&file_path, &options).map_err(|e| e.to_string())
+    });
+    let dataset = tokio::time::timeout(timeout, open)
+        .await
+        .map_err(|_| format!("Timed out opening dataset after {:?}", timeout))???;
+    Ok(dataset)
+}
+
+/// A dataset read from an in-memory `/vsimem/` file, which is removed when this is dropped.
+/// Dereferences to the [`Dataset`].
+pub struct MemDataset {
+    dataset: ManuallyDrop<Dataset>,
+    path: PathBuf,
+}
+
+impl MemDataset {
+    /// `/vsimem/` path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for MemDataset {
+    type Target = Dataset;
+
+    fn deref(&self) -> &Dataset {
+        &self.dataset
+    }
+}
+
+impl Drop for MemDataset {
+    fn drop(&mut self) {
+        // Close the dataset before its file goes away
+        unsafe { ManuallyDrop::drop(&mut self.dataset) };
+        if let Err(e) = gdal::vsi::unlink_mem_file(&self.path) {
+            tracing::warn!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Open an upload held in memory, e.g. a multipart HTTP body, without writing it to disk. Only
+/// the file name of `name` is kept; its extension helps GDAL pick a driver.
+pub fn open_dataset_from_bytes(
+    name: &str,
+    bytes: impl Into<Vec<u8>>,
+    options: &OpenDatasetOptions,
+) -> Result<MemDataset, Box<dyn Error>> {
+    let file_name = Path::new(name)
+        .file_name()
+        .ok_or_else(|| format!("Invalid file name: {}", name))?;
+    // A directory per upload so concurrent uploads with the same name do not clash
+    let path = Path::new("/vsimem")
+        .join(uuid::Uuid::new_v4().to_string())
+        .join(file_name);
+
+    gdal::vsi::create_mem_file(&path, bytes.into())?;
+    match open_dataset_with_options(&path, options) {
+        Ok(dataset) => Ok(MemDataset {
+            dataset: ManuallyDrop::new(dataset),
+            path,
+        }),
+        Err(e) => {
+            let _ = gdal::vsi::unlink_mem_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Where a dataset is read from, turned into a GDAL virtual file system path by
+/// [`VsiSource::to_vsi_path`] for [`open_dataset`] and friends. Sources nest, so a zip archive
+/// can itself live on S3 or behind a URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VsiSource {
+    /// A file on the local disk
+    Local { path: String },
+    /// An `http://` or `https://` URL, read with range requests through `/vsicurl/`
+    Http { url: String },
+    /// An S3 object, credentials coming from the usual `AWS_*` configuration
+    S3 { bucket: String, key: String },
+    /// A zip archive, optionally pointing at one entry inside it
+    Zip {
+        archive: Box<VsiSource>,
+        #[serde(default)]
+        entry: Option<String>,
+    },
+}
+
+/// Reject components that could smuggle in another virtual file system or leave the intended
+/// location
+fn validate_vsi_component(kind: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    if value.is_empty() {
+        return Err(format!("Empty {}", kind).into());
+    }
+    if value.chars().any(char::is_control) {
+        return Err(format!("{} contains control characters: {:?}", kind, value).into());
+    }
+    if value.contains("/vsi") {
+        return Err(format!("{} must not contain a GDAL virtual path: {}", kind, value).into());
+    }
+    if value.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err(format!("{} must not contain '..': {}", kind, value).into());
+    }
+    Ok(())
+}
+
+impl VsiSource {
+    /// GDAL path for this source, e.g. `/vsizip//vsis3/bucket/data.zip/roads.shp`
+    pub fn to_vsi_path(&self) -> Result<String, Box<dyn Error>> {
+        match self {
+            VsiSource::Local { path } => {
+                validate_vsi_component("Path", path)?;
+                Ok(path.clone())
+            }
+            VsiSource::Http { url } => {
+                validate_vsi_component("URL", url)?;
+                if !(url.starts_with("https://") || url.starts_with("http://")) {
+                    return Err(format!("URL must use http or https: {}", url).into());
+                }
+                Ok(format!("/vsicurl/{}", url))
+            }
+            VsiSource::S3 { bucket, key } => {
+                let valid_bucket = (3..=63).contains(&bucket.len())
+                    && bucket.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'
+                    });
+                if !valid_bucket {
+                    return Err(format!("Invalid S3 bucket name: {}", bucket).into());
+                }
+                validate_vsi_component("S3 key", key)?;
+                Ok(format!("/vsis3/{}/{}", bucket, key.trim_start_matches('/')))
+            }
+            VsiSource::Zip { archive, entry } => {
+                let archive = archive.to_vsi_path()?;
+                match entry {
+                    Some(entry) => {
+                        validate_vsi_component("Zip entry", entry)?;
+                        Ok(format!(
+                            "/vsizip/{}/{}",
+                            archive,
+                            entry.trim_start_matches('/')
+                        ))
+                    }
+                    None => Ok(format!("/vsizip/{}", archive)),
+                }
+            }
+        }
+    }
+}
+
+/// Where geometries come from in a CSV file or spreadsheet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CsvGeometry {
+    /// Points built from two coordinate columns, longitude/latitude or easting/northing. CSV
+    /// carries no CRS, so set [`crate::file::SchemaOptions::default_srid`] for anything other
+    /// than WGS 84.
+    Coordinates { x_column: String, y_column: String },
+    /// A column of WKT or hex-encoded WKB geometries, as exported by most databases. Types
+    /// may be mixed and are resolved by [`crate::file::GeometryPromotion`].
+    Column { column: String },
+}
+
+/// Field separator of a delimited text file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvSeparator {
+    /// Detect from the header line
+    #[default]
+    Auto,
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+/// Options for opening a CSV file with [`open_csv_dataset`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsvOptions {
+    pub geometry: CsvGeometry,
+    #[serde(default)]
+    pub separator: CsvSeparator,
+    /// Bytes read from the start of the file to infer field types, `None` for the whole file
+    #[serde(default = "default_sample_bytes")]
+    pub sample_bytes: Option<u64>,
+}
+
+fn default_sample_bytes() -> Option<u64> {
+    Some(1_000_000)
+}
+
+impl CsvOptions {
+    /// GDAL CSV driver open options for these settings
+    fn open_options(&self) -> Vec<String> {
+        let mut options = vec![
+            // Typed Integer/Real/Date fields instead of everything as String
+            "AUTODETECT_TYPE=YES".to_string(),
+            format!("AUTODETECT_SIZE_LIMIT={}", self.sample_bytes.unwrap_or(0)),
+            "EMPTY_STRING_AS_NULL=YES".to_string(),
+            // Coordinates live in the geometry, not duplicated as attributes
+            "KEEP_GEOM_COLUMNS=NO".to_string(),
+            format!(
+                "SEPARATOR={}",
+                match self.separator {
+                    CsvSeparator::Auto => "AUTO",
+                    CsvSeparator::Comma => "COMMA",
+                    CsvSeparator::Semicolon => "SEMICOLON",
+                    CsvSeparator::Tab => "TAB",
+                }
+            ),
+        ];
+        match &self.geometry {
+            CsvGeometry::Coordinates { x_column, y_column } => {
+                options.push(format!("X_POSSIBLE_NAMES={}", x_column));
+                options.push(format!("Y_POSSIBLE_NAMES={}", y_column));
+            }
+            // The CSV driver reads both WKT and hex WKB from a geometry column
+            CsvGeometry::Column { column } => {
+                options.push(format!("GEOM_POSSIBLE_NAMES={}", column));
+            }
+        }
+        options
+    }
+}
+
+/// Open a CSV or TSV file as a vector layer, inferring field types by sampling its rows
+pub fn open_csv_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &CsvOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    open_dataset_with_options(
+        file_path,
+        &OpenDatasetOptions {
+            open_options: options.open_options(),
+            allowed_drivers: Some(vec!["CSV".to_string()]),
+            kind: Some(DataKind::Vector),
+        },
+    )
+}
+
+/// Options for opening a shapefile with [`open_shapefile_dataset`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShapefileOptions {
+    /// Character encoding of the .dbf attributes, e.g. `CP1252`, `SHIFT_JIS` or `UTF-8`. When
+    /// `None` GDAL follows the .cpg sidecar, then the code page recorded in the .dbf header,
+    /// and otherwise assumes ISO-8859-1, which garbles files that are really UTF-8.
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+/// Encoding declared by the .cpg sidecar of a shapefile, e.g. `UTF-8` or `1252`, to show the
+/// user before they pick an override. `None` when there is no readable .cpg.
+pub fn shapefile_encoding<P: AsRef<Path>>(file_path: P) -> Option<String> {
+    let cpg = file_path.as_ref().with_extension("cpg");
+    let encoding = std::fs::read_to_string(&cpg)
+        .or_else(|_| std::fs::read_to_string(cpg.with_extension("CPG")))
+        .ok()?;
+    let encoding = encoding.trim();
+    (!encoding.is_empty()).then(|| encoding.to_string())
+}
+
+/// Open a shapefile, recoding its attributes to UTF-8 from the encoding in `options` or the one
+/// the file declares
+pub fn open_shapefile_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &ShapefileOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let mut open_options = Vec::new();
+    if let Some(encoding) = &options.encoding {
+        let valid = !encoding.is_empty()
+            && encoding
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(format!("Invalid encoding: {}", encoding).into());
+        }
+        open_options.push(format!("ENCODING={}", encoding));
+    }
+    open_dataset_with_options(
+        file_path,
+        &OpenDatasetOptions {
+            open_options,
+            allowed_drivers: Some(vec!["ESRI Shapefile".to_string()]),
+            kind: Some(DataKind::Vector),
+        },
+    )
+}
+
+/// How the first row of a spreadsheet is treated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum XlsxHeaders {
+    /// Use the first row as headers when its cells are text and the next row's are not
+    #[default]
+    Auto,
+    /// Always use the first row as headers
+    Force,
+    /// Treat the first row as data, naming fields Field1, Field2, ...
+    Disable,
+}
+
+/// Options for opening an Excel workbook with [`open_xlsx_dataset`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct XlsxOptions {
+    /// Sheet to read, the first sheet when `None`
+    #[serde(default)]
+    pub sheet: Option<String>,
+    #[serde(default)]
+    pub headers: XlsxHeaders,
+    /// Geometry source; a [`CsvGeometry::Column`] is read as WKT
+    pub geometry: CsvGeometry,
+}
+
+/// Escape text for use in XML content or attribute values
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Open one sheet of an XLSX workbook as a vector layer. The GDAL XLSX driver has no geometry
+/// support of its own, so the sheet is wrapped in an OGR VRT that builds geometries from the
+/// designated columns.
+pub fn open_xlsx_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &XlsxOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let file_path = std::path::absolute(file_path.as_ref())?;
+    let headers = match options.headers {
+        XlsxHeaders::Auto => "AUTO",
+        XlsxHeaders::Force => "FORCE",
+        XlsxHeaders::Disable => "DISABLE",
+    };
+
+    let sheet = match &options.sheet {
+        Some(sheet) => sheet.clone(),
+        None => {
+            let workbook = Dataset::open_ex(
+                &file_path,
+                DatasetOptions {
+                    open_flags: GdalOpenFlags::GDAL_OF_VECTOR,
+                    allowed_drivers: Some(&["XLSX"]),
+                    ..Default::default()
+                },
+            )?;
+            workbook.layer(0)?.name()
+        }
+    };
+
+    let geometry_field = match &options.geometry {
+        CsvGeometry::Coordinates { x_column, y_column } => format!(
+            r#"<GeometryField encoding="PointFromColumns" x="{}" y="{}"/>"#,
+            xml_escape(x_column),
+            xml_escape(y_column)
+        ),
+        CsvGeometry::Column { column } => format!(
+            r#"<GeometryField encoding="WKT" field="{}"/>"#,
+            xml_escape(column)
+        ),
+    };
+
+    let vrt = format!(
+        r#"<OGRVRTDataSource>
+            <OGRVRTLayer name="{sheet}">
+                <SrcDataSource relativeToVRT="0">{path}</SrcDataSource>
+                <OpenOptions>
+                    <OOI key="HEADERS">{headers}</OOI>
+                    <OOI key="FIELD_TYPES">AUTO</OOI>
+                </OpenOptions>
+                <SrcLayer>{sheet}</SrcLayer>
+                {geometry_field}
+            </OGRVRTLayer>
+        </OGRVRTDataSource>"#,
+        sheet = xml_escape(&sheet),
+        path = xml_escape(&file_path.to_string_lossy()),
+    );
+
+    let dataset = Dataset::open_ex(
+        vrt,
+        DatasetOptions {
+            open_flags: GdalOpenFlags::GDAL_OF_VECTOR,
+            allowed_drivers: Some(&["OGR_VRT"]),
+            ..Default::default()
+        },
+    )?;
+    Ok(dataset)
+}
+
+/// Field added by [`KmlLayout::Flatten`] holding the folder each placemark came from
+pub const KML_FOLDER_FIELD: &str = "folder_path";
+
+/// How the folders of a KML or KMZ file are turned into layers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KmlLayout {
+    /// All placemarks in one layer, with the folder name in [`KML_FOLDER_FIELD`]
+    #[default]
+    Flatten,
+    /// One layer per folder, as GDAL splits the document
+    PerFolder,
+}
+
+/// Options for opening a KML or KMZ file with [`open_kml_dataset`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KmlOptions {
+    #[serde(default)]
+    pub layout: KmlLayout,
+}
+
+/// Open a KML or KMZ file. GDAL makes a layer of every folder, including empty and nested
+/// ones, so by default the folders are merged back into a single layer through an OGR VRT
+/// union with fields from all of them.
+pub fn open_kml_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &KmlOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let file_path = std::path::absolute(file_path.as_ref())?;
+    let kml_options = OpenDatasetOptions {
+        allowed_drivers: Some(vec!["LIBKML".to_string(), "KML".to_string()]),
+        kind: Some(DataKind::Vector),
+        ..Default::default()
+    };
+    let dataset = open_dataset_with_options(&file_path, &kml_options)?;
+    if options.layout == KmlLayout::PerFolder {
+        return Ok(dataset);
+    }
+
+    let folders: Vec<String> = dataset
+        .layers()
+        .filter(|layer| layer.feature_count() > 0)
+        .map(|layer| layer.name())
+        .collect();
+    if folders.is_empty() {
+        return Err(format!("No placemarks in {}", file_path.display()).into());
+    }
+
+    let path = xml_escape(&file_path.to_string_lossy());
+    let source_layers: String = folders
+        .iter()
+        .map(|folder| {
+            format!(
+                r#"<OGRVRTLayer name="{folder}">
+                    <SrcDataSource relativeToVRT="0">{path}</SrcDataSource>
+                    <SrcLayer>{folder}</SrcLayer>
+                </OGRVRTLayer>"#,
+                folder = xml_escape(folder),
+            )
+        })
+        .collect();
+    let layer_name = file_path
+        .file_stem()
+        .map_or("placemarks".into(), |stem| stem.to_string_lossy());
+
+    let vrt = format!(
+        r#"<OGRVRTDataSource>
+            <OGRVRTUnionLayer name="{layer_name}">
+                {source_layers}
+                <SourceLayerFieldName>{KML_FOLDER_FIELD}</SourceLayerFieldName>
+                <FieldStrategy>Union</FieldStrategy>
+            </OGRVRTUnionLayer>
+        </OGRVRTDataSource>"#,
+        layer_name = xml_escape(&layer_name),
+    );
+
+    let dataset = Dataset::open_ex(
+        vrt,
+        DatasetOptions {
+            open_flags: GdalOpenFlags::GDAL_OF_VECTOR,
+            allowed_drivers: Some(&["OGR_VRT"]),
+            ..Default::default()
+        },
+    )?;
+    Ok(dataset)
+}
+
+/// Options for opening a GPX file with [`open_gpx_dataset`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpxOptions {
+    /// Also expose every recorded track point as a `track_points` layer. Off by default, as
+    /// a long recording holds far more points than anyone wants as separate features.
+    #[serde(default)]
+    pub include_track_points: bool,
+}
+
+/// GPX layers exposed by [`open_gpx_dataset`]: output name, GDAL layer and OGR SQL selecting
+/// the fields worth keeping under plain names
+const GPX_LAYERS: &[(&str, &str, &str)] = &[
+    (
+        "waypoints",
+        "waypoints",
+        r#"SELECT name, "desc" AS description, ele AS elevation, time, sym AS symbol, type
+        FROM waypoints"#,
+    ),
+    (
+        "routes",
+        "routes",
+        r#"SELECT name, "desc" AS description, number, type FROM routes"#,
+    ),
+    // Each segment of a track is one part of its multi-line geometry
+    (
+        "tracks",
+        "tracks",
+        r#"SELECT name, "desc" AS description, number, type FROM tracks"#,
+    ),
+    (
+        "track_points",
+        "track_points",
+        r#"SELECT track_fid AS track_id, track_seg_id AS segment, ele AS elevation, time
+        FROM track_points"#,
+    ),
+];
+
+/// Open a GPX file as separate point and line layers for waypoints, routes and tracks with
+/// elevation and time fields. Layers GDAL exposes that have no features in this file are left
+/// out, as are the many rarely used GPX attributes.
+pub fn open_gpx_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &GpxOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let file_path = std::path::absolute(file_path.as_ref())?;
+    let gpx = open_dataset_with_options(
+        &file_path,
+        &OpenDatasetOptions {
+            allowed_drivers: Some(vec!["GPX".to_string()]),
+            kind: Some(DataKind::Vector),
+            ..Default::default()
+        },
+    )?;
+
+    let path = xml_escape(&file_path.to_string_lossy());
+    let layers: String = GPX_LAYERS
+        .iter()
+        .filter(|(name, _, _)| options.include_track_points || *name != "track_points")
+        .filter(|(_, source, _)| {
+            gpx.layer_by_name(source)
+                .is_ok_and(|layer| layer.feature_count() > 0)
+        })
+        .map(|(name, _, sql)| {
+            format!(
+                r#"<OGRVRTLayer name="{name}">
+                    <SrcDataSource relativeToVRT="0">{path}</SrcDataSource>
+                    <SrcSQL>{sql}</SrcSQL>
+                </OGRVRTLayer>"#,
+                sql = xml_escape(sql),
+            )
+        })
+        .collect();
+    if layers.is_empty() {
+        return Err(format!("No waypoints, routes or tracks in {}", file_path.display()).into());
+    }
+
+    let dataset = Dataset::open_ex(
+        format!("<OGRVRTDataSource>{}</OGRVRTDataSource>", layers),
+        DatasetOptions {
+            open_flags: GdalOpenFlags::GDAL_OF_VECTOR,
+            allowed_drivers: Some(&["OGR_VRT"]),
+            ..Default::default()
+        },
+    )?;
+    Ok(dataset)
+}
+
+/// Open a TopoJSON file. GDAL rebuilds geometries from the shared arcs and makes a layer of
+/// each object in the topology, so every object collection can be ingested as its own layer.
+pub fn open_topojson_dataset<P: AsRef<Path>>(file_path: P) -> Result<Dataset, Box<dyn Error>> {
+    open_dataset_with_options(
+        file_path,
+        &OpenDatasetOptions {
+            allowed_drivers: Some(vec!["TopoJSON".to_string()]),
+            kind: Some(DataKind::Vector),
+            ..Default::default()
+        },
+    )
+}
+
+/// Geospatial file formats recognised by [`detect_format`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileType {
+    Shapefile,
+    GeoPackage,
+    /// GeoJSON, or newline-delimited GeoJSONSeq (`.geojsonl`, `.geojsons`), which GDAL reads
+    /// one feature at a time so files larger than memory can be ingested with a
+    /// [`ChunkPlan`](crate::conversion::ChunkPlan). Other extensions such as `.ndjson` can be
+    /// opened with `allowed_drivers: ["GeoJSONSeq"]`.
+    GeoJson,
+    TopoJson,
+    Csv,
+    Kml,
+    Kmz,
+    Gml,
+    Gpx,
+    FileGdb,
+    FlatGeobuf,
+    GeoParquet,
+    Xlsx,
+    GeoTiff,
+    Jpeg2000,
+    /// Zip archive, typically a zipped shapefile to be read through `/vsizip/`
+    Zip,
+    /// Tar archive, optionally gzipped, read through `/vsitar/`
+    Tar,
+    /// Opened by a GDAL driver without a dedicated variant
+    Other,
+}
+
+/// Whether a format holds vector features, raster bands or either
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataKind {
+    Vector,
+    Raster,
+    Both,
+}
+
+impl FileType {
+    /// Data a file of this type usually holds, `None` for archives
+    pub fn kind(&self) -> Option<DataKind> {
+        match self {
+            FileType::GeoTiff | FileType::Jpeg2000 => Some(DataKind::Raster),
+            FileType::GeoPackage => Some(DataKind::Both),
+            FileType::Zip | FileType::Tar | FileType::Other => None,
+            _ => Some(DataKind::Vector),
+        }
+    }
+
+    fn from_driver(short_name: &str) -> FileType {
+        match short_name {
+            "ESRI Shapefile" => FileType::Shapefile,
+            "GPKG" => FileType::GeoPackage,
+            "GeoJSON" | "GeoJSONSeq" => FileType::GeoJson,
+            "TopoJSON" => FileType::TopoJson,
+            "CSV" => FileType::Csv,
+            "KML" | "LIBKML" => FileType::Kml,
+            "GML" => FileType::Gml,
+            "GPX" => FileType::Gpx,
+            "OpenFileGDB" | "FileGDB" => FileType::FileGdb,
+            "FlatGeobuf" => FileType::FlatGeobuf,
+            "Parquet" => FileType::GeoParquet,
+            "XLSX" => FileType::Xlsx,
+            "GTiff" | "COG" => FileType::GeoTiff,
+            "JP2OpenJPEG" => FileType::Jpeg2000,
+            _ => FileType::Other,
+        }
+    }
+}
+
+/// Result of [`detect_format`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedFormat {
+    pub file_type: FileType,
+    /// Short name of the GDAL driver that recognised the file, if any did
+    pub driver: Option<String>,
+    pub kind: Option<DataKind>,
+}
+
+/// Identify a format from the first bytes of a file, with the extension (lowercase, without
+/// the dot) to tell zip-based and plain text formats apart. Returns `None` when nothing matches.
+pub fn sniff_format(bytes: &[u8], extension: Option<&str>) -> Option<FileType> {
+    match bytes {
+        [
+            b'S',
+            b'Q',
+            b'L',
+            b'i',
+            b't',
+            b'e',
+            b' ',
+            b'f',
+            b'o',
+            b'r',
+            b'm',
+            b'a',
+            b't',
+            b' ',
+            b'3',
+            0,
+            ..,
+        ] => {
+            return Some(FileType::GeoPackage);
+        }
+        // Shapefile main header: big-endian file code 9994
+        [0x00, 0x00, 0x27, 0x0a, ..] => return Some(FileType::Shapefile),
+        // Classic and BigTIFF, either byte order
+        [b'I', b'I', 42 | 43, 0, ..] | [b'M', b'M', 0, 42 | 43, ..] => {
+            return Some(FileType::GeoTiff);
+        }
+        [0x00, 0x00, 0x00, 0x0c, b'j', b'P', b' ', b' ', ..] => return Some(FileType::Jpeg2000),
+        [b'f', b'g', b'b', 3, ..] => return Some(FileType::FlatGeobuf),
+        [b'P', b'A', b'R', b'1', ..] => return Some(FileType::GeoParquet),
+        [b'P', b'K', 3, 4, ..] => {
+            return Some(match extension {
+                Some("xlsx") => FileType::Xlsx,
+                Some("kmz") => FileType::Kmz,
+                _ => FileType::Zip,
+            });
+        }
+        // Gzip is only taken for a tarball when named like one; `.gz` alone may be any file
+        [0x1f, 0x8b, ..] if extension == Some("tgz") => return Some(FileType::Tar),
+        _ if bytes.get(257..262) == Some(b"ustar") => return Some(FileType::Tar),
+        _ => {}
+    }
+
+    // Text formats: skip a UTF-8 byte order mark and leading whitespace
+    let text = String::from_utf8_lossy(bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes));
+    let text = text.trim_start();
+    if text.starts_with('{') {
+        // A topology names its type near the top, usually as the first member
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if compact.contains(r#""type":"Topology""#) {
+            return Some(FileType::TopoJson);
+        }
+        return Some(FileType::GeoJson);
+    }
+    if text.starts_with('\x1e') {
+        return Some(FileType::GeoJson);
+    }
+    if text.starts_with('<') {
+        let head = text.to_lowercase();
+        return if head.contains("<kml") {
+            Some(FileType::Kml)
+        } else if head.contains("<gpx") {
+            Some(FileType::Gpx)
+        } else if head.contains("gml") {
+            Some(FileType::Gml)
+        } else {
+            None
+        };
+    }
+    match extension {
+        Some("csv" | "tsv" | "txt") => Some(FileType::Csv),
+        _ => None,
+    }
+}
+
+/// Detect the format of a file, or of a directory such as a FileGDB, before choosing an ingest
+/// pipeline. GDAL's driver identification is tried first and magic bytes are the fallback.
+pub fn detect_format<P: AsRef<Path>>(file_path: P) -> Result<DetectedFormat, Box<dyn Error>> {
+    let file_path = file_path.as_ref();
+    let extension = file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+
+    gdal::DriverManager::register_all();
+    let c_path = std::ffi::CString::new(file_path.to_string_lossy().as_bytes())?;
+    let c_driver = unsafe {
+        gdal_sys::GDALIdentifyDriverEx(c_path.as_ptr(), 0, std::ptr::null(), std::ptr::null())
+    };
+    if !c_driver.is_null() {
+        let driver = unsafe { gdal::Driver::from_c_driver(c_driver) };
+        let short_name = driver.short_name();
+        let capability = |key: &str| driver.metadata_item(key, "").as_deref() == Some("YES");
+        let kind = match (capability("DCAP_VECTOR"), capability("DCAP_RASTER")) {
+            (true, true) => Some(DataKind::Both),
+            (true, false) => Some(DataKind::Vector),
+            (false, true) => Some(DataKind::Raster),
+            (false, false) => None,
+        };
+        let file_type = match (FileType::from_driver(&short_name), extension.as_deref()) {
+            (FileType::Kml, Some("kmz")) => FileType::Kmz,
+            (file_type, _) => file_type,
+        };
+        return Ok(DetectedFormat {
+            file_type,
+            driver: Some(short_name),
+            kind,
+        });
+    }
+
+    let mut head = Vec::with_capacity(1024);
+    if file_path.is_file() {
+        use std::io::Read;
+        std::fs::File::open(file_path)?
+            .take(1024)
+            .read_to_end(&mut head)?;
+    }
+    let file_type = sniff_format(&head, extension.as_deref())
+        .ok_or_else(|| format!("Unrecognised file format: {}", file_path.display()))?;
+    Ok(DetectedFormat {
+        file_type,
+        driver: None,
+        kind: file_type.kind(),
+    })
+}
+
+/// Extensions of files that hold a dataset, most preferred first when an archive has several
+const ARCHIVE_DATASET_EXTENSIONS: &[&str] = &[
+    "gpkg", "shp", "geojson", "geojsonl", "geojsons", "ndjson", "topojson", "json", "fgb",
+    "parquet", "kml", "gml", "gpx", "csv", "mbtiles", "tif", "tiff", "jp2",
+];
+
+/// Extensions that accompany a dataset of the same name, e.g. `roads.dbf` for `roads.shp`
+const SIDECAR_EXTENSIONS: &[&str] = &[
+    "dbf", "shx", "prj", "cpg", "sbn", "sbx", "qix", "shp.xml", "gpkg-wal", "gpkg-shm", "tfw",
+    "tifw", "aux.xml", "ovr", "j2w", "csvt",
+];
+
+/// One dataset found in an archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveDataset {
+    /// Path inside the archive, a directory for FileGDB
+    pub path: String,
+    /// Files that belong with it, e.g. the .dbf, .shx and .prj of a shapefile
+    pub sidecars: Vec<String>,
+}
+
+/// What [`inspect_archive`] found in a zip or tar archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveContents {
+    pub file_type: FileType,
+    /// GDAL path of the archive root, e.g. `/vsizip//uploads/roads.zip`
+    pub vsi_root: String,
+    /// Datasets in order of preference; the first is the one to ingest by default
+    pub datasets: Vec<ArchiveDataset>,
+}
+
+impl ArchiveContents {
+    pub fn primary(&self) -> Option<&ArchiveDataset> {
+        self.datasets.first()
+    }
+
+    /// GDAL path of a dataset inside the archive, for [`open_dataset`]
+    pub fn vsi_path(&self, dataset: &ArchiveDataset) -> String {
+        format!("{}/{}", self.vsi_root, dataset.path)
+    }
+}
+
+/// Relative path of an archive entry with only normal components, rejecting absolute paths
+/// and `..` that would escape an extraction directory (zip slip)
+fn safe_entry_path(entry: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let mut safe = PathBuf::new();
+    for component in entry.components() {
+        match component {
+            std::path::Component::Normal(part) => safe.push(part),
+            std::path::Component::CurDir => {}
+            _ => return Err(format!("Unsafe path in archive: {}", entry.display()).into()),
+        }
+    }
+    if safe.as_os_str().is_empty() {
+        return Err(format!("Empty path in archive: {}", entry.display()).into());
+    }
+    Ok(safe)
+}
+
+/// List the datasets in a zip, tar or tar.gz upload with their sidecars, without extracting it.
+/// macOS resource forks and hidden files are ignored.
+pub fn inspect_archive<P: AsRef<Path>>(file_path: P) -> Result<ArchiveContents, Box<dyn Error>> {
+    let file_path = std::path::absolute(file_path.as_ref())?;
+    let name = file_path.to_string_lossy().to_lowercase();
+    let file_type = if name.ends_with(".tar.gz") || name.ends_with(".tar") {
+        FileType::Tar
+    } else {
+        detect_format(&file_path)?.file_type
+    };
+    let vsi_root = match file_type {
+        FileType::Zip => format!("/vsizip/{}", file_path.display()),
+        FileType::Tar => format!("/vsitar/{}", file_path.display()),
+        other => return Err(format!("Not an archive: {:?}", other).into()),
+    };
+
+    let mut entries = Vec::new();
+    for entry in gdal::vsi::read_dir(&vsi_root, true)? {
+        let entry = safe_entry_path(&entry)?;
+        let hidden = entry.components().any(|component| {
+            let part = component.as_os_str().to_string_lossy();
+            part.starts_with('.') || part == "__MACOSX"
+        });
+        if !hidden {
+            entries.push(entry.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    entries.sort();
+
+    let extension_rank = |entry: &str| {
+        let extension = Path::new(entry)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+        ARCHIVE_DATASET_EXTENSIONS
+            .iter()
+            .position(|candidate| *candidate == extension)
+    };
+    let gdb_root = |entry: &str| {
+        // Byte-wise so the offset stays valid for names whose case mapping changes their length
+        let end = entry
+            .as_bytes()
+            .windows(".gdb/".len())
+            .position(|window| window.eq_ignore_ascii_case(b".gdb/"))?
+            + ".gdb".len();
+        Some(entry[..end].to_string())
+    };
+
+    let mut datasets: Vec<(usize, ArchiveDataset)> = Vec::new();
+    // FileGDB directories rank after single-file formats but before rasters
+    let gdb_rank = ARCHIVE_DATASET_EXTENSIONS
+        .iter()
+        .position(|extension| *extension == "mbtiles")
+        .unwrap_or_default();
+    for entry in &entries {
+        if let Some(root) = gdb_root(entry) {
+            match datasets
+                .iter_mut()
+                .find(|(_, dataset)| dataset.path == root)
+            {
+                Some((_, dataset)) => dataset.sidecars.push(entry.clone()),
+                None => datasets.push((
+                    gdb_rank,
+                    ArchiveDataset {
+                        path: root,
+                        sidecars: vec![entry.clone()],
+                    },
+                )),
+            }
+            continue;
+        }
+        let Some(rank) = extension_rank(entry) else {
+            continue;
+        };
+        // `roads.shp` is accompanied by `roads.dbf`, `roads.shp.xml`, ...
+        let stem = &entry[..entry.rfind('.').unwrap_or(entry.len())];
+        let sidecars = entries
+            .iter()
+            .filter(|other| {
+                let (other_bytes, stem) = (other.as_bytes(), stem.as_bytes());
+                SIDECAR_EXTENSIONS.iter().any(|extension| {
+                    other_bytes.len() == stem.len() + extension.len() + 1
+                        && other_bytes[..stem.len()].eq_ignore_ascii_case(stem)
+                        && other_bytes[stem.len()] == b'.'
+                        && other_bytes[stem.len() + 1..].eq_ignore_ascii_case(extension.as_bytes())
+                }) || other.to_lowercase() == format!("{}.aux.xml", entry.to_lowercase())
+            })
+            .cloned()
+            .collect();
+        datasets.push((
+            rank,
+            ArchiveDataset {
+                path: entry.clone(),
+                sidecars,
+            },
+        ));
+    }
+    datasets.sort_by_key(|(rank, _)| *rank);
+
+    Ok(ArchiveContents {
+        file_type,
+        vsi_root,
+        datasets: datasets.into_iter().map(|(_, dataset)| dataset).collect(),
+    })
+}
+
+/// Open the primary dataset of an archive in place through `/vsizip/` or `/vsitar/`
+pub fn open_archive_dataset<P: AsRef<Path>>(
+    file_path: P,
+    options: &OpenDatasetOptions,
+) -> Result<Dataset, Box<dyn Error>> {
+    let contents = inspect_archive(&file_path)?;
+    let primary = contents.primary().ok_or_else(|| {
+        format!(
+            "No dataset found in archive: {}",
+            file_path.as_ref().display()
+        )
+    })?;
+    open_dataset_with_options(contents.vsi_path(primary), options)
+}
+
+/// Extract a dataset and its sidecars from an archive into `destination`, for drivers or tools
+/// that cannot read through GDAL's virtual file systems. Returns the path of the extracted
+/// dataset. Entries that would land outside `destination` are rejected.
+pub fn extract_archive_dataset<P: AsRef<Path>>(
+    contents: &ArchiveContents,
+    dataset: &ArchiveDataset,
+    destination: P,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let destination = destination.as_ref();
+    let mut files = dataset.sidecars.clone();
+    if !files.contains(&dataset.path) && !dataset.path.to_lowercase().ends_with(".gdb") {
+        files.push(dataset.path.clone());
+    }
+
+    for file in &files {
+        let target = destination.join(safe_entry_path(Path::new(file))?);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let source = std::ffi::CString::new(format!("{}/{}", contents.vsi_root, file))?;
+        let target_c = std::ffi::CString::new(target.to_string_lossy().as_bytes())?;
+        let result = unsafe { gdal_sys::CPLCopyFile(target_c.as_ptr(), source.as_ptr()) };
+        if result != 0 {
+            return Err(format!("Failed to extract {} from archive", file).into());
+        }
+    }
+
+    Ok(destination.join(safe_entry_path(Path::new(&dataset.path))?))
+}
+
+/// Limits enforced by [`validate_file`]; `None` leaves a limit unchecked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationLimits {
+    /// Largest accepted file in bytes
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    /// Most features accepted in any one layer
+    #[serde(default)]
+    pub max_feature_count: Option<u64>,
+    /// Most attribute fields accepted in any one layer
+    #[serde(default)]
+    pub max_field_count: Option<usize>,
+    #[serde(default)]
+    pub allowed_formats: Option<Vec<FileType>>,
+    /// PostGIS geometry types accepted as a layer's declared type, e.g. `MULTIPOLYGON`. Layers
+    /// declaring no specific type (`GEOMETRY`) are always accepted.
+    #[serde(default)]
+    pub allowed_geometry_types: Option<Vec<String>>,
+}
+
+/// A problem found by [`validate_file`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValidationIssue {
+    FileTooLarge {
+        size: u64,
+        limit: u64,
+    },
+    FormatNotAllowed {
+        file_type: FileType,
+    },
+    /// A vector format with no layers at all
+    NoLayers,
+    TooManyFeatures {
+        layer: String,
+        count: u64,
+        limit: u64,
+    },
+    TooManyFields {
+        layer: String,
+        count: usize,
+        limit: usize,
+    },
+    UnsupportedGeometryType {
+        layer: String,
+        geometry_type: String,
+    },
+    EmptyLayer {
+        layer: String,
+    },
+    /// Neither the layer nor the file declares a CRS, so WGS 84 will be assumed
+    MissingCrs {
+        layer: Option<String>,
+    },
+    /// An attribute-only table
+    NoGeometry {
+        layer: String,
+    },
+}
+
+impl ValidationIssue {
+    /// Whether the issue rejects the file; the rest are warnings to show the user
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self,
+            ValidationIssue::EmptyLayer { .. }
+                | ValidationIssue::MissingCrs { .. }
+                | ValidationIssue::NoGeometry { .. }
+        )
+    }
+}
+
+/// Result of [`validate_file`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub file_size: u64,
+    /// Detected format, `None` when validation stopped before detection
+    pub format: Option<DetectedFormat>,
+    pub layers: Vec<crate::file::LayerInfo>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether the file can go ahead to ingest
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(ValidationIssue::is_error)
+    }
+}
+
+/// Check an upload against `limits` before any database work happens. Failed limits and
+/// suspicious content are collected in the report rather than returned as errors; an error
+/// means the file could not be inspected at all.
+pub fn validate_file<P: AsRef<Path>>(
+    file_path: P,
+    limits: &ValidationLimits,
+) -> Result<ValidationReport, Box<dyn Error>> {
+    let file_path = file_path.as_ref();
+    let metadata = std::fs::metadata(file_path)?;
+    // Directory formats such as FileGDB are measured by their files
+    let file_size = if metadata.is_dir() {
+        std::fs::read_dir(file_path)?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    } else {
+        metadata.len()
+    };
+    let mut report = ValidationReport {
+        file_size,
+        format: None,
+        layers: Vec::new(),
+        issues: Vec::new(),
+    };
+
+    // Oversized or unexpected files are not handed to GDAL at all
+    if let Some(limit) = limits.max_file_size.filter(|limit| file_size > *limit) {
+        report.issues.push(ValidationIssue::FileTooLarge {
+            size: file_size,
+            limit,
+        });
+        return Ok(report);
+    }
+    let format = detect_format(file_path)?;
+    report.format = Some(format.clone());
+    if let Some(allowed) = &limits.allowed_formats
+        && !allowed.contains(&format.file_type)
+    {
+        report.issues.push(ValidationIssue::FormatNotAllowed {
+            file_type: format.file_type,
+        });
+        return Ok(report);
+    }
+
+    let dataset = open_dataset_with_options(
+        file_path,
+        &OpenDatasetOptions {
+            allowed_drivers: format.driver.clone().map(|driver| vec![driver]),
+            ..Default::default()
+        },
+    )?;
+
+    if format.kind == Some(DataKind::Raster) {
+        if dataset.spatial_ref().is_err() {
+            report
+                .issues
+                .push(ValidationIssue::MissingCrs { layer: None });
+        }
+        return Ok(report);
+    }
+
+    if dataset.layer_count() == 0 {
+        report.issues.push(ValidationIssue::NoLayers);
+    }
+    for layer in dataset.layers() {
+        let name = layer.name();
+        let defn = layer.defn();
+        let declared_type = defn.geometry_type();
+        let geometry_type = crate::file::postgis_geometry_type(declared_type);
+        let feature_count = layer.feature_count();
+        let field_count = defn.fields().count();
+
+        if let Some(limit) = limits
+            .max_feature_count
+            .filter(|limit| feature_count > *limit)
+        {
+            report.issues.push(ValidationIssue::TooManyFeatures {
+                layer: name.clone(),
+                count: feature_count,
+                limit,
+            });
+        }
+        if let Some(limit) = limits.max_field_count.filter(|limit| field_count > *limit) {
+            report.issues.push(ValidationIssue::TooManyFields {
+                layer: name.clone(),
+                count: field_count,
+                limit,
+            });
+        }
+        if declared_type == gdal_sys::OGRwkbGeometryType::wkbNone {
+            report.issues.push(ValidationIssue::NoGeometry {
+                layer: name.clone(),
+            });
+        } else {
+            if let Some(allowed) = &limits.allowed_geometry_types
+                && geometry_type != "GEOMETRY"
+                && !allowed.iter().any(|allowed| allowed == geometry_type)
+            {
+                report
+                    .issues
+                    .push(ValidationIssue::UnsupportedGeometryType {
+                        layer: name.clone(),
+                        geometry_type: geometry_type.to_string(),
+                    });
+            }
+            if layer.spatial_ref().is_none() {
+                report.issues.push(ValidationIssue::MissingCrs {
+                    layer: Some(name.clone()),
+                });
+            }
+        }
+        if feature_count == 0 {
+            report.issues.push(ValidationIssue::EmptyLayer {
+                layer: name.clone(),
+            });
+        }
+
+        report.layers.push(crate::file::LayerInfo {
+            name,
+            geometry_type: geometry_type.to_string(),
+            feature_count,
+            srid: layer.spatial_ref().and_then(|srs| srs.auth_code().ok()),
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(path: &str) -> VsiSource {
+        VsiSource::Local {
+            path: path.to_string(),
+        }
+    }
+
+    fn zip(archive: VsiSource, entry: Option<&str>) -> VsiSource {
+        VsiSource::Zip {
+            archive: Box::new(archive),
+            entry: entry.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn vsi_paths() {
+        assert_eq!(
+            local("/data/roads.gpkg").to_vsi_path().unwrap(),
+            "/data/roads.gpkg"
+        );
+        let http = VsiSource::Http {
+            url: "https://example.com/dem.tif".to_string(),
+        };
+        assert_eq!(
+            http.to_vsi_path().unwrap(),
+            "/vsicurl/https://example.com/dem.tif"
+        );
+        let s3 = VsiSource::S3 {
+            bucket: "my-bucket".to_string(),
+            key: "/exports/data.zip".to_string(),
+        };
+        assert_eq!(
+            s3.to_vsi_path().unwrap(),
+            "/vsis3/my-bucket/exports/data.zip"
+        );
+        assert_eq!(
+            zip(s3, Some("/roads.shp")).to_vsi_path().unwrap(),
+            "/vsizip//vsis3/my-bucket/exports/data.zip/roads.shp"
+        );
+        assert_eq!(
+            zip(local("/data/a.zip"), None).to_vsi_path().unwrap(),
+            "/vsizip//data/a.zip"
+        );
+    }
+
+    #[test]
+    fn vsi_paths_reject_parent_segments() {
+        assert!(local("data/../../etc/passwd").to_vsi_path().is_err());
+        assert!(local("data\\..\\secret").to_vsi_path().is_err());
+        assert!(local("..").to_vsi_path().is_err());
+        assert!(
+            zip(local("/data/a.zip"), Some("../../etc/passwd"))
+                .to_vsi_path()
+                .is_err()
+        );
+        let s3 = VsiSource::S3 {
+            bucket: "bucket".to_string(),
+            key: "a/../../other".to_string(),
+        };
+        assert!(s3.to_vsi_path().is_err());
+        // Dots inside a segment are fine
+        assert!(local("/data/roads..gpkg").to_vsi_path().is_ok());
+    }
+
+    #[test]
+    fn vsi_paths_reject_smuggled_file_systems() {
+        assert!(
+            local("/vsicurl/http://internal/secret")
+                .to_vsi_path()
+                .is_err()
+        );
+        let http = VsiSource::Http {
+            url: "https://example.com/vsis3/bucket/key".to_string(),
+        };
+        assert!(http.to_vsi_path().is_err());
+        assert!(
+            zip(local("/data/a.zip"), Some("/vsimem/other"))
+                .to_vsi_path()
+                .is_err()
+        );
+        // The nested archive is validated too
+        assert!(
+            zip(local("/vsistdin/"), Some("a.shp"))
+                .to_vsi_path()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn vsi_paths_reject_invalid_components() {
+        assert!(local("").to_vsi_path().is_err());
+        assert!(local("/data/a\nb.shp").to_vsi_path().is_err());
+        let ftp = VsiSource::Http {
+            url: "ftp://example.com/a.tif".to_string(),
+        };
+        assert!(ftp.to_vsi_path().is_err());
+        for bucket in ["ab", "My_Bucket", &"a".repeat(64)] {
+            let s3 = VsiSource::S3 {
+                bucket: bucket.to_string(),
+                key: "key".to_string(),
+            };
+            assert!(s3.to_vsi_path().is_err(), "{}", bucket);
+        }
+    }
+
+    #[test]
+    fn entry_paths_keep_normal_components() {
+        assert_eq!(
+            safe_entry_path(Path::new("data/roads.shp")).unwrap(),
+            PathBuf::from("data/roads.shp")
+        );
+        assert_eq!(
+            safe_entry_path(Path::new("./data/./roads.shp")).unwrap(),
+            PathBuf::from("data/roads.shp")
+        );
+    }
+
+    #[test]
+    fn entry_paths_reject_zip_slip() {
+        assert!(safe_entry_path(Path::new("../evil.sh")).is_err());
+        assert!(safe_entry_path(Path::new("data/../../evil.sh")).is_err());
+        assert!(safe_entry_path(Path::new("data/..")).is_err());
+        assert!(safe_entry_path(Path::new("/etc/passwd")).is_err());
+        assert!(safe_entry_path(Path::new("")).is_err());
+        assert!(safe_entry_path(Path::new("./")).is_err());
+    }
+}
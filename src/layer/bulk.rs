@@ -0,0 +1,133 @@
+use super::{DeletedLayer, LayerEvent, LayerEventSink, LayerGroupStore, LayerStatus, LayerUpdate};
+use crate::Layer;
+use crate::metadata::MetadataStore;
+use anyhow::{Result, anyhow};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Outcome of [`delete_many`]
+#[derive(Clone, Debug, Default)]
+pub struct BulkDeletion {
+    /// Layers that existed and whose metadata was deleted
+    pub deleted: Vec<Uuid>,
+    /// Deleted layers whose data table or cached tiles could not be removed, with the error.
+    /// Their metadata is gone, so the leftovers have to be cleaned up by hand.
+    pub cleanup_failed: Vec<(Uuid, String)>,
+}
+
+/// Delete many layers at once. Metadata is removed in a single transaction, so either every
+/// layer disappears from listings or none does; the data tables recorded in the deleted
+/// metadata are then dropped through the connector. A failed drop or eviction does not stop
+/// the others and is reported in [`BulkDeletion::cleanup_failed`].
+pub async fn delete_many<C>(
+    workspace_id: Uuid,
+    ids: &[Uuid],
+    connector: &C,
+    tile_cache: Option<&dyn crate::TileCache>,
+    store: &dyn MetadataStore,
+) -> Result<BulkDeletion>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    let deleted = store.delete_many(workspace_id, ids).await?;
+
+    let mut deletion = BulkDeletion::default();
+    for layer in deleted {
+        if let Err(e) = cleanup_deleted(&layer, connector, tile_cache).await {
+            tracing::warn!("Failed to clean up deleted layer {}: {}", layer.id, e);
+            deletion.cleanup_failed.push((layer.id, e.to_string()));
+        }
+        deletion.deleted.push(layer.id);
+    }
+
+    Ok(deletion)
+}
+
+async fn cleanup_deleted<C>(
+    layer: &DeletedLayer,
+    connector: &C,
+    tile_cache: Option<&dyn crate::TileCache>,
+) -> Result<()>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    if let Some(source) = &layer.source {
+        connector.drop_layer(source).await?;
+    }
+    if let Some(tile_cache) = tile_cache {
+        tile_cache.evict_layer(&layer.id).await?;
+    }
+    Ok(())
+}
+
+/// Move many layers, each given with its current status, to `to` in a single transaction.
+/// Every transition is checked before anything is written and the batch fails as a whole if
+/// any layer has changed status in the meantime. Events are published after commit.
+pub async fn update_status_many(
+    workspace_id: Uuid,
+    layers: &[(Uuid, LayerStatus)],
+    to: LayerStatus,
+    events: Option<&dyn LayerEventSink>,
+    store: &dyn MetadataStore,
+) -> Result<Vec<Layer>> {
+    let transitions = layers
+        .iter()
+        .map(|(id, current)| Ok((*id, current.transition(to)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let updates: Vec<(Uuid, LayerUpdate)> = transitions
+        .iter()
+        .map(|(id, transition)| {
+            let changes = LayerUpdate {
+                transition: Some(transition.clone()),
+                ..Default::default()
+            };
+            (*id, changes)
+        })
+        .collect();
+    let updated = store.update_many(workspace_id, &updates).await?;
+
+    if let Some(events) = events {
+        for (layer_id, transition) in transitions {
+            events.publish(LayerEvent::StatusChanged {
+                layer_id,
+                transition,
+            });
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Place many layers in a group in a single transaction, in the order given starting at
+/// `position`, or remove them all from their groups when `group_id` is `None`
+pub async fn move_to_group_many<G>(
+    workspace_id: Uuid,
+    layer_ids: &[Uuid],
+    group_id: Option<Uuid>,
+    position: i32,
+    pool: &PgPool,
+) -> Result<()>
+where
+    G: LayerGroupStore,
+{
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+    for (offset, layer_id) in layer_ids.iter().enumerate() {
+        G::move_layer(
+            workspace_id,
+            *layer_id,
+            group_id,
+            position + offset as i32,
+            &mut *tx,
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit layer moves: {}", e))
+}
@@ -0,0 +1,128 @@
+use crate::connector::postgis::PostgisConnector;
+use crate::conversion::{ChunkPlan, FeatureChunk};
+use crate::file::LayerSchema;
+use crate::metadata::MetadataStore;
+use anyhow::{Result, anyhow};
+use gdal::Dataset;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// `kind` of the `gridwalk.jobs` rows that each ingest one [`FeatureChunk`]
+pub const INGEST_CHUNK_JOB: &str = "ingest_chunk";
+
+/// How long a claimed chunk job may stay `running` before another worker may claim it again,
+/// so chunks of a crashed worker are not stuck forever
+pub const CHUNK_JOB_LEASE: Duration = Duration::from_secs(30 * 60);
+
+/// A queued chunk of a chunked import
+#[derive(Debug, Clone)]
+pub struct ChunkJob {
+    pub id: Uuid,
+    pub layer_id: Uuid,
+    pub chunk: FeatureChunk,
+}
+
+/// Queue a job for every chunk of `plan` that the layer has no job for yet, and return the
+/// layer's chunk jobs that are not completed, in chunk order. Calling it again after a restart,
+/// or from several workers at once, queues nothing twice and returns exactly the chunks left
+/// to ingest.
+pub async fn queue_chunk_jobs(
+    workspace_id: Uuid,
+    layer_id: Uuid,
+    plan: &ChunkPlan,
+    store: &dyn MetadataStore,
+) -> Result<Vec<ChunkJob>> {
+    let chunks: Vec<FeatureChunk> = plan.chunks().collect();
+    store
+        .queue_chunk_jobs(workspace_id, layer_id, &chunks)
+        .await
+}
+
+/// Claim and ingest one chunk into the table of `schema` in `namespace`, through `pool` on the
+/// connector's database. A store that can join the insert transaction (the Postgres one)
+/// commits the chunk's rows and its job's completion together, so after a crash the chunk is
+/// either fully loaded or claimable again and run from scratch; with other stores a crash
+/// between the two commits runs the chunk again. A failure is recorded on the job and
+/// returned. Returns the number of features inserted, or `None` when the job could not be
+/// claimed because it is completed or another worker holds it, or another worker completed it
+/// first.
+pub async fn run_chunk_job(
+    job: &ChunkJob,
+    dataset: Dataset,
+    schema: &LayerSchema,
+    namespace: &str,
+    store: &dyn MetadataStore,
+    pool: &PgPool,
+) -> Result<Option<u64>> {
+    if !store.claim_chunk_job(job.id, CHUNK_JOB_LEASE).await? {
+        return Ok(None);
+    }
+
+    let result = ingest_chunk(job, dataset, schema, namespace, store, pool).await;
+    if let Err(e) = &result {
+        store.fail_chunk_job(job.id, &e.to_string()).await?;
+    }
+    result
+}
+
+async fn ingest_chunk(
+    job: &ChunkJob,
+    dataset: Dataset,
+    schema: &LayerSchema,
+    namespace: &str,
+    store: &dyn MetadataStore,
+    pool: &PgPool,
+) -> Result<Option<u64>> {
+    // Statements are built on a blocking thread, as GDAL reads are synchronous
+    let chunk = job.chunk.clone();
+    let (schema_owned, namespace_owned) = (schema.clone(), namespace.to_string());
+    let statements = tokio::task::spawn_blocking(move || {
+        let mut statements = Vec::new();
+        chunk
+            .for_each_feature(&dataset, |feature, defn| {
+                let statement = PostgisConnector::feature_to_insert_statement(
+                    feature,
+                    defn,
+                    &namespace_owned,
+                    &schema_owned.layer_name,
+                    None,
+                    schema_owned.srid,
+                    Some(&schema_owned),
+                )
+                .map_err(|e| {
+                    Box::new(std::io::Error::other(e.to_string()))
+                        as Box<dyn std::error::Error + Send>
+                })?;
+                statements.extend(statement);
+                Ok(())
+            })
+            .map(|()| statements)
+            .map_err(|e| anyhow!("Failed to read chunk {}: {}", chunk.index, e))
+    })
+    .await
+    .map_err(|e| anyhow!("Chunk task failed: {}", e))??;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+    for statement in &statements {
+        sqlx::raw_sql(statement)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to insert chunk {}: {}", job.chunk.index, e))?;
+    }
+    let completed = store.complete_chunk_job_with(job.id, &mut *tx).await?;
+    if completed == Some(false) {
+        return Ok(None);
+    }
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit chunk {}: {}", job.chunk.index, e))?;
+    if completed.is_none() {
+        store.complete_chunk_job(job.id).await?;
+    }
+
+    Ok(Some(statements.len() as u64))
+}
@@ -1,29 +1,112 @@
-use anyhow::Result;
+use crate::Layer;
+use crate::metadata::MetadataStore;
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
 use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
-#[derive(Copy, Clone, Debug, Display, Serialize, Deserialize, EnumString, PartialEq)]
-pub enum Srid {
-    #[serde(rename = "3857")]
-    #[strum(serialize = "3857")]
-    EPSG3857,
+/// EPSG code of a layer's coordinate reference system. Serialized as a string such as `"3857"`;
+/// bare numbers and `EPSG:` prefixed codes are accepted when parsing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Srid(pub u32);
 
-    #[serde(rename = "4326")]
-    #[strum(serialize = "4326")]
-    EPSG4326,
+impl Srid {
+    /// Web Mercator
+    pub const EPSG3857: Srid = Srid(3857);
+    /// WGS 84 longitude/latitude
+    pub const EPSG4326: Srid = Srid(4326);
+
+    pub fn code(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Srid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Srid {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        let code = trimmed
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("EPSG:"))
+            .map_or(trimmed, |_| &trimmed[5..]);
+        code.parse::<u32>()
+            .ok()
+            .and_then(|code| Srid::try_from(code).ok())
+            .ok_or_else(|| anyhow!("Invalid SRID: {}", s))
+    }
 }
 
 impl From<Srid> for i32 {
     fn from(srid: Srid) -> i32 {
-        match srid {
-            Srid::EPSG3857 => 3857,
-            Srid::EPSG4326 => 4326,
+        srid.0 as i32
+    }
+}
+
+/// Every SRID conversion validates through here; 0 is PostGIS's "unknown" and not a CRS
+impl TryFrom<u32> for Srid {
+    type Error = anyhow::Error;
+
+    fn try_from(code: u32) -> Result<Self> {
+        if code == 0 {
+            return Err(anyhow!("Invalid SRID: {}", code));
         }
+        Ok(Srid(code))
     }
 }
 
-#[derive(Clone, Debug, Display, Serialize, Deserialize, EnumString)]
+impl TryFrom<i32> for Srid {
+    type Error = anyhow::Error;
+
+    fn try_from(code: i32) -> Result<Self> {
+        u32::try_from(code)
+            .ok()
+            .and_then(|code| Srid::try_from(code).ok())
+            .ok_or_else(|| anyhow!("Invalid SRID: {}", code))
+    }
+}
+
+impl Serialize for Srid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Srid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Code(u32),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Code(code) => Srid::try_from(code).map_err(serde::de::Error::custom),
+            Repr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Full definition of a CRS that has no EPSG code, registered with the connector to obtain a
+/// [`Srid`] it can transform and tile with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", content = "definition", rename_all = "snake_case")]
+pub enum CrsDefinition {
+    /// OGC WKT, version 1 or 2
+    Wkt(String),
+    /// PROJ string, e.g. `+proj=tmerc +lat_0=0 ...`
+    Proj(String),
+}
+
+#[derive(Copy, Clone, Debug, Display, Serialize, Deserialize, EnumString, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum LayerStatus {
     Uploading,
@@ -34,38 +117,579 @@ pub enum LayerStatus {
     Failed,
 }
 
+impl LayerStatus {
+    /// Whether a layer in this status may move to `to`. Failed, errored and cancelled layers
+    /// can be retried, and ready layers re-processed, but nothing returns to uploading from a
+    /// live state.
+    pub fn can_transition_to(self, to: LayerStatus) -> bool {
+        use LayerStatus::*;
+        matches!(
+            (self, to),
+            (Uploading, Processing | Error | Failed | Cancelled)
+                | (Processing, Ready | Error | Failed | Cancelled)
+                | (Ready, Processing)
+                | (Error | Failed | Cancelled, Uploading | Processing)
+        )
+    }
+
+    /// Validate a move to `to`, returning the timestamped transition to store
+    pub fn transition(self, to: LayerStatus) -> Result<LayerStatusTransition> {
+        if !self.can_transition_to(to) {
+            return Err(anyhow!(
+                "Invalid layer status transition: {} -> {}",
+                self,
+                to
+            ));
+        }
+        Ok(LayerStatusTransition {
+            from: self,
+            to,
+            at: chrono::Utc::now(),
+        })
+    }
+}
+
+/// A validated status change, created by [`LayerStatus::transition`]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LayerStatusTransition {
+    pub from: LayerStatus,
+    pub to: LayerStatus,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Change notifications published by layer operations
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LayerEvent {
+    StatusChanged {
+        layer_id: Uuid,
+        transition: LayerStatusTransition,
+    },
+}
+
+/// Receiver of [`LayerEvent`]s, e.g. a channel feeding websockets or a job queue
+pub trait LayerEventSink: Send + Sync {
+    fn publish(&self, event: LayerEvent);
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct LayerSummary {
     pub id: Uuid,
+    /// Workspace (team or tenant) the layer belongs to
+    pub workspace_id: Uuid,
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
     pub name: String,
     pub status: LayerStatus,
+    /// Status changes with their timestamps, oldest first
+    #[serde(default)]
+    pub status_history: Vec<LayerStatusTransition>,
+    /// Fraction of the current processing step completed, from 0.0 to 1.0
+    #[serde(default)]
+    pub progress: Option<f32>,
+    /// Human-readable detail on the current status, e.g. the step in progress or an error
+    #[serde(default)]
+    pub status_message: Option<String>,
+    /// Free-form labels for organising layers (project, theme, sensitivity)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Arbitrary host-defined metadata
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    /// Statistics stored after ingest, see [`refresh_layer_stats`]
+    #[serde(default)]
+    pub stats: Option<crate::LayerStats>,
+    /// Where the layer's data lives, used by maintenance tasks such as [`purge_expired`]
+    #[serde(default)]
+    pub source: Option<crate::LayerSource>,
+    /// When a temporary layer should be removed by [`purge_expired`]; `None` keeps it
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-/// Core trait that all layer types must implement
+/// One page of results with the total number of matches, so UIs can render pagination without
+/// a separate count query (implementations can use `count(*) OVER ()`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: u64, limit: u64, offset: u64) -> Self {
+        Page {
+            items,
+            total,
+            limit,
+            offset,
+        }
+    }
+
+    /// Offset of the following page, `None` on the last page
+    pub fn next_offset(&self) -> Option<u64> {
+        let next = self.offset + self.items.len() as u64;
+        (next < self.total).then_some(next)
+    }
+}
+
+/// Column used to order [`LayerCore::list`] results
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerSortField {
+    Name,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Search, filter and sort parameters for [`LayerCore::list`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LayerQuery {
+    /// Case-insensitive substring match on the layer name
+    #[serde(default)]
+    pub name_contains: Option<String>,
+    #[serde(default)]
+    pub status: Option<LayerStatus>,
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
+    /// Only layers carrying every one of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub sort_by: LayerSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+impl LayerQuery {
+    /// Append ` AND <condition>` for each filter set, against `name`, `status`, `owner_id`, `tags`
+    /// (a text array) and `created_at` columns. Status is compared using its `Display` form.
+    pub fn push_conditions(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(name_contains) = &self.name_contains {
+            let escaped = name_contains
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            builder.push(" AND name ILIKE ");
+            builder.push_bind(format!("%{}%", escaped));
+        }
+        if let Some(status) = &self.status {
+            builder.push(" AND status = ");
+            builder.push_bind(status.to_string());
+        }
+        if let Some(owner_id) = self.owner_id {
+            builder.push(" AND owner_id = ");
+            builder.push_bind(owner_id);
+        }
+        if !self.tags.is_empty() {
+            builder.push(" AND tags @> ");
+            builder.push_bind(self.tags.clone());
+        }
+        if let Some(created_after) = self.created_after {
+            builder.push(" AND created_at > ");
+            builder.push_bind(created_after);
+        }
+    }
+
+    /// `ORDER BY` clause for the requested sort, with id as a stable tie-breaker
+    pub fn order_by(&self) -> String {
+        let column = match self.sort_by {
+            LayerSortField::Name => "name",
+            LayerSortField::CreatedAt => "created_at",
+            LayerSortField::UpdatedAt => "updated_at",
+        };
+        let direction = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        format!(" ORDER BY {column} {direction}, id {direction}")
+    }
+}
+
+/// Changes applied by [`LayerCore::update`]; fields left as `None` are unchanged
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LayerUpdate {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Status change to apply: implementations set the status to `to`, append the transition
+    /// to the history and should only match rows still in status `from`
+    #[serde(default)]
+    pub transition: Option<LayerStatusTransition>,
+    #[serde(default)]
+    pub progress: Option<f32>,
+    #[serde(default)]
+    pub status_message: Option<String>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub stats: Option<crate::LayerStats>,
+    /// Schema extracted at ingest time, returned by [`LayerCore::schema`]
+    #[serde(default)]
+    pub schema: Option<crate::file::LayerSchema>,
+    /// PNG preview returned by [`LayerCore::thumbnail`], see [`refresh_layer_thumbnail`]
+    #[serde(default)]
+    pub thumbnail: Option<Vec<u8>>,
+    #[serde(default)]
+    pub source: Option<crate::LayerSource>,
+    /// `Some(None)` clears the expiry, making a temporary layer permanent
+    #[serde(default)]
+    pub expires_at: Option<Option<chrono::DateTime<chrono::Utc>>>,
+}
+
+/// Metadata removed by [`LayerCore::delete`], with the source the data table is dropped from
+#[derive(Clone, Debug)]
+pub struct DeletedLayer {
+    pub id: Uuid,
+    /// `None` for layers deleted before any data was ingested
+    pub source: Option<crate::LayerSource>,
+}
+
+/// Core trait that all layer types must implement.
+///
+/// Every lookup is scoped to a workspace so one deployment can isolate layers per team or
+/// tenant; a layer id from another workspace behaves as if it did not exist.
 pub trait LayerCore: Sized {
     fn save<'e, E>(&self, executor: E) -> impl std::future::Future<Output = Result<()>> + Send
     where
         E: sqlx::Executor<'e, Database = sqlx::Postgres>;
 
     fn list<'e, E>(
+        workspace_id: Uuid,
+        query: &LayerQuery,
         limit: u64,
         offset: u64,
         executor: E,
-    ) -> impl std::future::Future<Output = Result<Vec<Self>>> + Send
+    ) -> impl std::future::Future<Output = Result<Page<LayerSummary>>> + Send
     where
         E: sqlx::Executor<'e, Database = sqlx::Postgres>;
 
-    fn get<'e, E>(id: Uuid, executor: E) -> impl std::future::Future<Output = Result<Self>> + Send
+    fn get<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Self>> + Send
     where
         E: sqlx::Executor<'e, Database = sqlx::Postgres>;
 
     fn exists<'e, E>(
+        workspace_id: Uuid,
         id: Uuid,
         executor: E,
     ) -> impl std::future::Future<Output = Result<bool>> + Send
     where
         E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Schema stored at ingest time, so field lists don't require introspecting the data table.
+    /// `None` if no schema has been stored.
+    fn schema<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Option<crate::file::LayerSchema>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// PNG preview stored with the layer, kept out of [`LayerSummary`] so listings stay small.
+    /// `None` if no thumbnail has been rendered.
+    fn thumbnail<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Option<Vec<u8>>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Copy the layer's metadata to a new layer named `new_name` backed by `source` in the same
+    /// workspace, with a fresh id, empty status history and new timestamps, and return the copy
+    fn duplicate<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        new_name: &str,
+        source: &crate::LayerSource,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Self>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Layers in any workspace whose `expires_at` is at or before `now`, oldest expiry first
+    fn expired<'e, E>(
+        now: chrono::DateTime<chrono::Utc>,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerSummary>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Apply `changes` to the layer's metadata and return the updated layer
+    fn update<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        changes: &LayerUpdate,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Self>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Delete the layer's metadata, returning `None` if no layer has this id
+    fn delete<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Option<DeletedLayer>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+}
+
+/// Delete a layer completely: its metadata, the data table held by the connector and any cached
+/// tiles. Metadata goes first so the layer disappears from listings even if cleanup fails; the
+/// table dropped is the one the deleted metadata recorded.
+pub async fn delete_layer<C>(
+    workspace_id: Uuid,
+    id: Uuid,
+    connector: &C,
+    tile_cache: Option<&dyn crate::TileCache>,
+    store: &dyn MetadataStore,
+) -> Result<bool>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    let Some(deleted) = store.delete(workspace_id, id).await? else {
+        return Ok(false);
+    };
+
+    if let Some(source) = &deleted.source {
+        connector.drop_layer(source).await?;
+    }
+    if let Some(tile_cache) = tile_cache {
+        tile_cache.evict_layer(&id).await?;
+    }
+
+    Ok(true)
+}
+
+/// Remove every expired temporary layer along with its data table, returning the layers
+/// removed. Each table is dropped before its metadata, and dropping is idempotent, so a layer
+/// whose metadata delete fails is simply purged again on the next run. A failure is logged and
+/// the remaining layers are still purged. Layers without a recorded source are skipped and
+/// kept, since their data table cannot be found to drop it. Intended to run periodically from
+/// a maintenance job.
+pub async fn purge_expired<C>(store: &dyn MetadataStore, connector: &C) -> Result<Vec<LayerSummary>>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    let expired = store.expired(chrono::Utc::now()).await?;
+
+    let mut purged = Vec::with_capacity(expired.len());
+    for layer in expired {
+        let Some(source) = &layer.source else {
+            tracing::warn!(
+                "Expired layer {} has no source to drop, skipping it; remove it by hand",
+                layer.id
+            );
+            continue;
+        };
+        if let Err(e) = connector.drop_layer(source).await {
+            tracing::warn!("Failed to drop table of expired layer {}: {}", layer.id, e);
+            continue;
+        }
+        match store.delete(layer.workspace_id, layer.id).await {
+            Ok(Some(_)) => purged.push(layer),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to delete expired layer {}: {}", layer.id, e),
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Move a layer from its `current` status to `to`, rejecting illegal moves, and publish a
+/// [`LayerEvent::StatusChanged`] once stored
+pub async fn transition_layer_status(
+    workspace_id: Uuid,
+    id: Uuid,
+    current: LayerStatus,
+    to: LayerStatus,
+    events: Option<&dyn LayerEventSink>,
+    store: &dyn MetadataStore,
+) -> Result<Layer> {
+    let transition = current.transition(to)?;
+    let changes = LayerUpdate {
+        transition: Some(transition.clone()),
+        ..Default::default()
+    };
+    let layer = store.update(workspace_id, id, &changes).await?;
+
+    if let Some(events) = events {
+        events.publish(LayerEvent::StatusChanged {
+            layer_id: id,
+            transition,
+        });
+    }
+    Ok(layer)
+}
+
+/// Store ingest progress on a layer; `progress` is clamped to 0.0-1.0
+pub async fn report_layer_progress(
+    workspace_id: Uuid,
+    id: Uuid,
+    progress: f32,
+    status_message: Option<String>,
+    store: &dyn MetadataStore,
+) -> Result<Layer> {
+    let changes = LayerUpdate {
+        progress: Some(progress.clamp(0.0, 1.0)),
+        status_message,
+        ..Default::default()
+    };
+    store.update(workspace_id, id, &changes).await
+}
+
+/// Compute statistics for a layer through the connector and store them with its metadata.
+/// `fields` are the numeric fields to summarise.
+pub async fn refresh_layer_stats<C>(
+    workspace_id: Uuid,
+    id: Uuid,
+    source: &crate::LayerSource,
+    fields: &[String],
+    connector: &C,
+    store: &dyn MetadataStore,
+) -> Result<Layer>
+where
+    C: crate::VectorConnector + ?Sized,
+{
+    let stats = connector.compute_layer_stats(source, fields).await?;
+    let changes = LayerUpdate {
+        stats: Some(stats),
+        ..Default::default()
+    };
+    store.update(workspace_id, id, &changes).await
+}
+
+/// Render a thumbnail of the layer through the connector and store it with its metadata
+pub async fn refresh_layer_thumbnail<C>(
+    workspace_id: Uuid,
+    id: Uuid,
+    source: &crate::LayerSource,
+    options: &crate::ThumbnailOptions,
+    connector: &C,
+    store: &dyn MetadataStore,
+) -> Result<Layer>
+where
+    C: crate::VectorConnector + ?Sized,
+{
+    let thumbnail = connector.render_thumbnail(source, options).await?;
+    let changes = LayerUpdate {
+        thumbnail: Some(thumbnail),
+        ..Default::default()
+    };
+    store.update(workspace_id, id, &changes).await
+}
+
+/// Copy a layer's table and metadata so it can be edited without touching the original. The
+/// table is copied first and dropped again if the metadata copy fails.
+pub async fn duplicate_layer<C>(
+    workspace_id: Uuid,
+    id: Uuid,
+    source: &crate::LayerSource,
+    new_name: &str,
+    connector: &C,
+    store: &dyn MetadataStore,
+) -> Result<(Layer, crate::LayerSource)>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    let copied = connector.copy_layer(source, new_name).await?;
+
+    match store.duplicate(workspace_id, id, new_name, &copied).await {
+        Ok(layer) => Ok((layer, copied)),
+        Err(e) => {
+            connector.drop_layer(&copied).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Rename a layer and its backing table together. The table is renamed first and renamed back
+/// if the metadata update fails, so the two never drift apart.
+pub async fn rename_layer<C>(
+    workspace_id: Uuid,
+    id: Uuid,
+    source: &crate::LayerSource,
+    new_name: &str,
+    connector: &C,
+    store: &dyn MetadataStore,
+) -> Result<(Layer, crate::LayerSource)>
+where
+    C: crate::ConnectorBase + ?Sized,
+{
+    let crate::LayerSource::Database { name: old_name, .. } = source;
+    let renamed = connector.rename_layer(source, new_name).await?;
+
+    let changes = LayerUpdate {
+        name: Some(new_name.to_string()),
+        source: Some(renamed.clone()),
+        ..Default::default()
+    };
+    match store.update(workspace_id, id, &changes).await {
+        Ok(layer) => Ok((layer, renamed)),
+        Err(e) => {
+            connector.rename_layer(&renamed, old_name).await?;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srid_from_str() {
+        assert_eq!("4326".parse::<Srid>().unwrap(), Srid::EPSG4326);
+        assert_eq!(" EPSG:3857 ".parse::<Srid>().unwrap(), Srid::EPSG3857);
+        assert_eq!("epsg:27700".parse::<Srid>().unwrap(), Srid(27700));
+        for invalid in [
+            "0", "EPSG:0", "EPSG:", "EPSG", "", "-4326", "4326.5", "WGS84",
+        ] {
+            assert!(invalid.parse::<Srid>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn srid_from_integers() {
+        assert!(Srid::try_from(0u32).is_err());
+        assert!(Srid::try_from(0i32).is_err());
+        assert!(Srid::try_from(-1i32).is_err());
+        assert_eq!(Srid::try_from(4326i32).unwrap(), Srid::EPSG4326);
+    }
+
+    #[test]
+    fn srid_serde() {
+        let parse = |json: &str| serde_json::from_str::<Srid>(json);
+        assert_eq!(parse("4326").unwrap(), Srid::EPSG4326);
+        assert_eq!(parse("\"3857\"").unwrap(), Srid::EPSG3857);
+        assert_eq!(parse("\"EPSG:27700\"").unwrap(), Srid(27700));
+        for invalid in ["0", "\"0\"", "\"EPSG:\"", "\"EPSG:0\"", "-1", "null"] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(serde_json::to_string(&Srid::EPSG4326).unwrap(), "\"4326\"");
+    }
 }
@@ -0,0 +1,162 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Folder in a workspace's table of contents; groups nest through `parent_id`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LayerGroup {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Enclosing group, `None` at the top level
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    /// Sort position among siblings
+    pub position: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Placement of a layer inside a group
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LayerGroupMember {
+    pub group_id: Uuid,
+    pub layer_id: Uuid,
+    /// Sort position among the group's layers
+    pub position: i32,
+}
+
+/// A group with its sub-groups and layers, each sorted by position
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LayerGroupNode {
+    pub group: LayerGroup,
+    pub children: Vec<LayerGroupNode>,
+    pub layer_ids: Vec<Uuid>,
+}
+
+impl LayerGroup {
+    /// Assemble flat group and membership lists into a sorted tree of top-level groups.
+    /// Groups whose parent is missing are treated as top level.
+    pub fn build_tree(
+        groups: Vec<LayerGroup>,
+        members: Vec<LayerGroupMember>,
+    ) -> Vec<LayerGroupNode> {
+        let ids: Vec<Uuid> = groups.iter().map(|group| group.id).collect();
+
+        let mut layers: HashMap<Uuid, Vec<LayerGroupMember>> = HashMap::new();
+        for member in members {
+            layers.entry(member.group_id).or_default().push(member);
+        }
+
+        let mut children: HashMap<Option<Uuid>, Vec<LayerGroup>> = HashMap::new();
+        for group in groups {
+            let parent = group.parent_id.filter(|parent| ids.contains(parent));
+            children.entry(parent).or_default().push(group);
+        }
+
+        fn build(
+            parent: Option<Uuid>,
+            children: &mut HashMap<Option<Uuid>, Vec<LayerGroup>>,
+            layers: &mut HashMap<Uuid, Vec<LayerGroupMember>>,
+        ) -> Vec<LayerGroupNode> {
+            let mut groups = children.remove(&parent).unwrap_or_default();
+            groups.sort_by(|a, b| a.position.cmp(&b.position).then(a.name.cmp(&b.name)));
+            groups
+                .into_iter()
+                .map(|group| {
+                    let mut members = layers.remove(&group.id).unwrap_or_default();
+                    members.sort_by_key(|member| member.position);
+                    LayerGroupNode {
+                        children: build(Some(group.id), children, layers),
+                        layer_ids: members.into_iter().map(|member| member.layer_id).collect(),
+                        group,
+                    }
+                })
+                .collect()
+        }
+
+        build(None, &mut children, &mut layers)
+    }
+
+    /// Check that moving `group_id` under `new_parent` would not place a group inside itself
+    pub fn validate_move(
+        groups: &[LayerGroup],
+        group_id: Uuid,
+        new_parent: Option<Uuid>,
+    ) -> Result<()> {
+        let parents: HashMap<Uuid, Option<Uuid>> = groups
+            .iter()
+            .map(|group| (group.id, group.parent_id))
+            .collect();
+
+        // Bounded walk up the ancestors, so corrupt cyclic data cannot loop forever
+        let mut current = new_parent;
+        for _ in 0..=parents.len() {
+            match current {
+                Some(id) if id == group_id => {
+                    return Err(anyhow!("Cannot move a group inside itself"));
+                }
+                Some(id) => current = parents.get(&id).copied().flatten(),
+                None => return Ok(()),
+            }
+        }
+        Err(anyhow!("Group hierarchy contains a cycle"))
+    }
+}
+
+/// Storage of layer groups and memberships, implemented by host applications
+pub trait LayerGroupStore {
+    fn save_group<'e, E>(
+        group: &LayerGroup,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<()>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    fn list_groups<'e, E>(
+        workspace_id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerGroup>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    fn list_members<'e, E>(
+        workspace_id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerGroupMember>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Delete a group; its sub-groups and layers move up to its parent
+    fn delete_group<'e, E>(
+        workspace_id: Uuid,
+        group_id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<bool>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Re-parent and reorder a group; callers should check [`LayerGroup::validate_move`] first
+    fn move_group<'e, E>(
+        workspace_id: Uuid,
+        group_id: Uuid,
+        parent_id: Option<Uuid>,
+        position: i32,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<()>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Place a layer in a group at `position`, or remove it from its group when `group_id` is
+    /// `None`
+    fn move_layer<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        group_id: Option<Uuid>,
+        position: i32,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<()>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+}
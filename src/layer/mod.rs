@@ -1,3 +1,17 @@
+mod bulk;
+mod chunk;
 mod core;
+mod group;
+mod model;
+mod permission;
+mod style;
+mod version;
 
+pub use bulk::*;
+pub use chunk::*;
 pub use core::*;
+pub use group::*;
+pub use model::*;
+pub use permission::*;
+pub use style::*;
+pub use version::*;
@@ -0,0 +1,673 @@
+use super::{
+    DeletedLayer, LayerCore, LayerLineage, LayerQuery, LayerStatus, LayerStatusTransition,
+    LayerSummary, LayerUpdate, LayerVersion, LayerVersionStore, Page,
+};
+use crate::file::LayerSchema;
+use crate::{LayerSource, LayerStats};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Columns of `gridwalk.layers` read into a [`LayerRow`]
+const LAYER_COLUMNS: &str = "id, workspace_id, owner_id, name, description, status, \
+    status_history, progress, status_message, connector_id, tags, metadata, stats, schema, \
+    source, expires_at, created_at, updated_at";
+
+/// Layer metadata stored in the `gridwalk.layers` table created by
+/// [`migrations::initialize`](crate::migrations::initialize)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Layer {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    #[serde(default)]
+    pub owner_id: Option<Uuid>,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub status: LayerStatus,
+    #[serde(default)]
+    pub status_history: Vec<LayerStatusTransition>,
+    #[serde(default)]
+    pub progress: Option<f32>,
+    #[serde(default)]
+    pub status_message: Option<String>,
+    /// Host identifier of the connector holding the layer's data
+    #[serde(default)]
+    pub connector_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub stats: Option<LayerStats>,
+    #[serde(default)]
+    pub schema: Option<LayerSchema>,
+    #[serde(default)]
+    pub source: Option<LayerSource>,
+    /// When a temporary layer should be removed, see [`purge_expired`](super::purge_expired)
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct LayerRow {
+    id: Uuid,
+    workspace_id: Uuid,
+    owner_id: Option<Uuid>,
+    name: String,
+    description: Option<String>,
+    status: String,
+    status_history: Json<Vec<LayerStatusTransition>>,
+    progress: Option<f32>,
+    status_message: Option<String>,
+    connector_id: Option<String>,
+    tags: Vec<String>,
+    metadata: Json<serde_json::Value>,
+    stats: Option<Json<LayerStats>>,
+    schema: Option<Json<LayerSchema>>,
+    source: Option<Json<LayerSource>>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TryFrom<LayerRow> for Layer {
+    type Error = anyhow::Error;
+
+    fn try_from(row: LayerRow) -> Result<Self> {
+        Ok(Layer {
+            id: row.id,
+            workspace_id: row.workspace_id,
+            owner_id: row.owner_id,
+            name: row.name,
+            description: row.description,
+            status: LayerStatus::from_str(&row.status)
+                .map_err(|_| anyhow!("Unknown layer status '{}'", row.status))?,
+            status_history: row.status_history.0,
+            progress: row.progress,
+            status_message: row.status_message,
+            connector_id: row.connector_id,
+            tags: row.tags,
+            metadata: row.metadata.0,
+            stats: row.stats.map(|stats| stats.0),
+            schema: row.schema.map(|schema| schema.0),
+            source: row.source.map(|source| source.0),
+            expires_at: row.expires_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+impl Layer {
+    /// A new layer in the `Uploading` state
+    pub fn new(workspace_id: Uuid, name: impl Into<String>) -> Self {
+        let now = chrono::Utc::now();
+        Layer {
+            id: Uuid::new_v4(),
+            workspace_id,
+            owner_id: None,
+            name: name.into(),
+            description: None,
+            status: LayerStatus::Uploading,
+            status_history: Vec::new(),
+            progress: None,
+            status_message: None,
+            connector_id: None,
+            tags: Vec::new(),
+            metadata: serde_json::Value::Object(Default::default()),
+            stats: None,
+            schema: None,
+            source: None,
+            expires_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn summary(&self) -> LayerSummary {
+        LayerSummary {
+            id: self.id,
+            workspace_id: self.workspace_id,
+            owner_id: self.owner_id,
+            name: self.name.clone(),
+            status: self.status,
+            status_history: self.status_history.clone(),
+            progress: self.progress,
+            status_message: self.status_message.clone(),
+            tags: self.tags.clone(),
+            metadata: self.metadata.clone(),
+            stats: self.stats.clone(),
+            source: self.source.clone(),
+            expires_at: self.expires_at,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct LayerListRow {
+    #[sqlx(flatten)]
+    layer: LayerRow,
+    total_count: i64,
+    row_number: i64,
+}
+
+impl LayerCore for Layer {
+    async fn save<'e, E>(&self, executor: E) -> Result<()>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        sqlx::query(
+            "INSERT INTO gridwalk.layers (
+                id, workspace_id, owner_id, name, description, status, status_history, progress,
+                status_message, connector_id, tags, metadata, stats, schema, source, expires_at,
+                created_at, updated_at
+            ) VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, now()
+            )
+            ON CONFLICT (id) DO UPDATE SET
+                owner_id = EXCLUDED.owner_id,
+                name = EXCLUDED.name,
+                description = EXCLUDED.description,
+                status = EXCLUDED.status,
+                status_history = EXCLUDED.status_history,
+                progress = EXCLUDED.progress,
+                status_message = EXCLUDED.status_message,
+                connector_id = EXCLUDED.connector_id,
+                tags = EXCLUDED.tags,
+                metadata = EXCLUDED.metadata,
+                stats = EXCLUDED.stats,
+                schema = EXCLUDED.schema,
+                source = EXCLUDED.source,
+                expires_at = EXCLUDED.expires_at,
+                updated_at = now()
+            WHERE gridwalk.layers.workspace_id = EXCLUDED.workspace_id",
+        )
+        .bind(self.id)
+        .bind(self.workspace_id)
+        .bind(self.owner_id)
+        .bind(&self.name)
+        .bind(&self.description)
+        .bind(self.status.to_string())
+        .bind(Json(&self.status_history))
+        .bind(self.progress)
+        .bind(&self.status_message)
+        .bind(&self.connector_id)
+        .bind(&self.tags)
+        .bind(Json(&self.metadata))
+        .bind(self.stats.as_ref().map(Json))
+        .bind(self.schema.as_ref().map(Json))
+        .bind(self.source.as_ref().map(Json))
+        .bind(self.expires_at)
+        .bind(self.created_at)
+        .execute(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to save layer '{}': {}", self.name, e))?;
+        Ok(())
+    }
+
+    async fn list<'e, E>(
+        workspace_id: Uuid,
+        query: &LayerQuery,
+        limit: u64,
+        offset: u64,
+        executor: E,
+    ) -> Result<Page<LayerSummary>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        // The first row is always returned so the total is known even past the last page, and
+        // is only listed when it falls inside the requested window
+        let first = offset.saturating_add(1).min(i64::MAX as u64);
+        let last = offset.saturating_add(limit).min(i64::MAX as u64);
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT * FROM (
+                SELECT {}, count(*) OVER () AS total_count,
+                    row_number() OVER ({}) AS row_number
+                FROM gridwalk.layers
+                WHERE workspace_id = ",
+            LAYER_COLUMNS,
+            query.order_by()
+        ));
+        builder.push_bind(workspace_id);
+        query.push_conditions(&mut builder);
+        builder.push(") ranked WHERE row_number = 1 OR row_number BETWEEN ");
+        builder.push_bind(first as i64);
+        builder.push(" AND ");
+        builder.push_bind(last as i64);
+        builder.push(" ORDER BY row_number");
+
+        let rows = builder
+            .build_query_as::<LayerListRow>()
+            .fetch_all(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to list layers: {}", e))?;
+
+        let total = rows.first().map_or(0, |row| row.total_count as u64);
+        let mut items = Vec::with_capacity(rows.len());
+        for row in rows {
+            if (first..=last).contains(&(row.row_number as u64)) {
+                items.push(Layer::try_from(row.layer)?.summary());
+            }
+        }
+
+        Ok(Page::new(items, total, limit, offset))
+    }
+
+    async fn get<'e, E>(workspace_id: Uuid, id: Uuid, executor: E) -> Result<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let query = format!(
+            "SELECT {} FROM gridwalk.layers WHERE workspace_id = $1 AND id = $2",
+            LAYER_COLUMNS
+        );
+        sqlx::query_as::<_, LayerRow>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch layer {}: {}", id, e))?
+            .ok_or_else(|| anyhow!("Layer {} not found", id))?
+            .try_into()
+    }
+
+    async fn exists<'e, E>(workspace_id: Uuid, id: Uuid, executor: E) -> Result<bool>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let (exists,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM gridwalk.layers WHERE workspace_id = $1 AND id = $2)",
+        )
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to check layer {}: {}", id, e))?;
+        Ok(exists)
+    }
+
+    async fn schema<'e, E>(workspace_id: Uuid, id: Uuid, executor: E) -> Result<Option<LayerSchema>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let (schema,): (Option<Json<LayerSchema>>,) = sqlx::query_as(
+            "SELECT schema FROM gridwalk.layers WHERE workspace_id = $1 AND id = $2",
+        )
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch schema of layer {}: {}", id, e))?
+        .ok_or_else(|| anyhow!("Layer {} not found", id))?;
+        Ok(schema.map(|schema| schema.0))
+    }
+
+    async fn thumbnail<'e, E>(workspace_id: Uuid, id: Uuid, executor: E) -> Result<Option<Vec<u8>>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let (thumbnail,): (Option<Vec<u8>>,) = sqlx::query_as(
+            "SELECT thumbnail FROM gridwalk.layers WHERE workspace_id = $1 AND id = $2",
+        )
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch thumbnail of layer {}: {}", id, e))?
+        .ok_or_else(|| anyhow!("Layer {} not found", id))?;
+        Ok(thumbnail)
+    }
+
+    async fn duplicate<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        new_name: &str,
+        source: &LayerSource,
+        executor: E,
+    ) -> Result<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let query = format!(
+            "INSERT INTO gridwalk.layers (
+                id, workspace_id, owner_id, name, description, status, status_history, progress,
+                status_message, connector_id, tags, metadata, stats, schema, thumbnail, source,
+                expires_at
+            )
+            SELECT $3, workspace_id, owner_id, $4, description, status, '[]'::jsonb, progress,
+                status_message, connector_id, tags, metadata, stats, schema, thumbnail, $5,
+                expires_at
+            FROM gridwalk.layers
+            WHERE workspace_id = $1 AND id = $2
+            RETURNING {}",
+            LAYER_COLUMNS
+        );
+        sqlx::query_as::<_, LayerRow>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .bind(Uuid::new_v4())
+            .bind(new_name)
+            .bind(Json(source))
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to duplicate layer {}: {}", id, e))?
+            .ok_or_else(|| anyhow!("Layer {} not found", id))?
+            .try_into()
+    }
+
+    async fn expired<'e, E>(
+        now: chrono::DateTime<chrono::Utc>,
+        executor: E,
+    ) -> Result<Vec<LayerSummary>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let query = format!(
+            "SELECT {} FROM gridwalk.layers WHERE expires_at <= $1 ORDER BY expires_at",
+            LAYER_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, LayerRow>(&query)
+            .bind(now)
+            .fetch_all(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to list expired layers: {}", e))?;
+
+        rows.into_iter()
+            .map(|row| Layer::try_from(row).map(|layer| layer.summary()))
+            .collect()
+    }
+
+    async fn update<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        changes: &LayerUpdate,
+        executor: E,
+    ) -> Result<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let mut builder =
+            QueryBuilder::<Postgres>::new("UPDATE gridwalk.layers SET updated_at = now()");
+        if let Some(name) = &changes.name {
+            builder.push(", name = ");
+            builder.push_bind(name.clone());
+        }
+        if let Some(description) = &changes.description {
+            builder.push(", description = ");
+            builder.push_bind(description.clone());
+        }
+        if let Some(transition) = &changes.transition {
+            builder.push(", status = ");
+            builder.push_bind(transition.to.to_string());
+            builder.push(", status_history = status_history || ");
+            builder.push_bind(Json(vec![transition.clone()]));
+        }
+        if let Some(progress) = changes.progress {
+            builder.push(", progress = ");
+            builder.push_bind(progress);
+        }
+        if let Some(status_message) = &changes.status_message {
+            builder.push(", status_message = ");
+            builder.push_bind(status_message.clone());
+        }
+        if let Some(tags) = &changes.tags {
+            builder.push(", tags = ");
+            builder.push_bind(tags.clone());
+        }
+        if let Some(metadata) = &changes.metadata {
+            builder.push(", metadata = ");
+            builder.push_bind(Json(metadata.clone()));
+        }
+        if let Some(stats) = &changes.stats {
+            builder.push(", stats = ");
+            builder.push_bind(Json(stats.clone()));
+        }
+        if let Some(schema) = &changes.schema {
+            builder.push(", schema = ");
+            builder.push_bind(Json(schema.clone()));
+        }
+        if let Some(thumbnail) = &changes.thumbnail {
+            builder.push(", thumbnail = ");
+            builder.push_bind(thumbnail.clone());
+        }
+        if let Some(source) = &changes.source {
+            builder.push(", source = ");
+            builder.push_bind(Json(source.clone()));
+        }
+        if let Some(expires_at) = changes.expires_at {
+            builder.push(", expires_at = ");
+            builder.push_bind(expires_at);
+        }
+        builder.push(" WHERE workspace_id = ");
+        builder.push_bind(workspace_id);
+        builder.push(" AND id = ");
+        builder.push_bind(id);
+        // A concurrent status change makes the transition stale, so it must not apply
+        if let Some(transition) = &changes.transition {
+            builder.push(" AND status = ");
+            builder.push_bind(transition.from.to_string());
+        }
+        builder.push(format!(" RETURNING {}", LAYER_COLUMNS));
+
+        builder
+            .build_query_as::<LayerRow>()
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to update layer {}: {}", id, e))?
+            .ok_or_else(|| match &changes.transition {
+                Some(transition) => anyhow!(
+                    "Layer {} not found or no longer in status {}",
+                    id,
+                    transition.from
+                ),
+                None => anyhow!("Layer {} not found", id),
+            })?
+            .try_into()
+    }
+
+    async fn delete<'e, E>(
+        workspace_id: Uuid,
+        id: Uuid,
+        executor: E,
+    ) -> Result<Option<DeletedLayer>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let deleted: Option<(Option<Json<LayerSource>>,)> = sqlx::query_as(
+            "DELETE FROM gridwalk.layers WHERE workspace_id = $1 AND id = $2 RETURNING source",
+        )
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to delete layer {}: {}", id, e))?;
+        Ok(deleted.map(|(source,)| DeletedLayer {
+            id,
+            source: source.map(|source| source.0),
+        }))
+    }
+}
+
+/// Columns of `gridwalk.layer_versions` as a [`LayerVersion`] tuple
+type VersionRow = (
+    Uuid,
+    i32,
+    String,
+    Option<Json<LayerLineage>>,
+    chrono::DateTime<chrono::Utc>,
+);
+
+fn version_from_row(
+    (layer_id, version, table_name, lineage, created_at): VersionRow,
+) -> LayerVersion {
+    LayerVersion {
+        layer_id,
+        version: version as u32,
+        table_name,
+        lineage: lineage.map(|lineage| lineage.0),
+        created_at,
+    }
+}
+
+impl LayerVersionStore for Layer {
+    async fn create_version<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        lineage: Option<&LayerLineage>,
+        executor: E,
+    ) -> Result<LayerVersion>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        // Table names follow LayerVersion::table_name_for: the source table's name, cut to the
+        // longest run of whole characters that leaves room for the suffix in 63 bytes
+        sqlx::query_as::<_, VersionRow>(
+            "INSERT INTO gridwalk.layer_versions (layer_id, version, table_name, lineage)
+            SELECT l.id, next.version, base.name || next.suffix, $2
+            FROM gridwalk.layers l,
+            LATERAL (
+                SELECT COALESCE(max(v.version), 0) + 1 AS version,
+                    '_v' || (COALESCE(max(v.version), 0) + 1) AS suffix
+                FROM gridwalk.layer_versions v
+                WHERE v.layer_id = l.id
+            ) next,
+            LATERAL (
+                SELECT string_agg(c.chr, '' ORDER BY c.n) AS name
+                FROM (
+                    SELECT chr, n, sum(octet_length(chr)) OVER (ORDER BY n) AS bytes
+                    FROM regexp_split_to_table(l.source->>'name', '')
+                        WITH ORDINALITY AS chars(chr, n)
+                ) c
+                WHERE c.bytes <= 63 - octet_length(next.suffix)
+            ) base
+            WHERE l.id = $1 AND l.workspace_id = $3 AND base.name IS NOT NULL
+            RETURNING layer_id, version, table_name, lineage, created_at",
+        )
+        .bind(layer_id)
+        .bind(lineage.map(Json))
+        .bind(workspace_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to create version of layer {}: {}", layer_id, e))?
+        .map(version_from_row)
+        .ok_or_else(|| anyhow!("Layer {} not found or has no source table", layer_id))
+    }
+
+    async fn list_versions<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        executor: E,
+    ) -> Result<Vec<LayerVersion>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM gridwalk.layer_versions v
+            JOIN gridwalk.layers l ON l.id = v.layer_id
+            WHERE v.layer_id = $1 AND l.workspace_id = $2
+            ORDER BY v.version DESC",
+        )
+        .bind(layer_id)
+        .bind(workspace_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to list versions of layer {}: {}", layer_id, e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+
+    async fn get_version<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        version: Option<u32>,
+        executor: E,
+    ) -> Result<LayerVersion>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM gridwalk.layer_versions v
+            JOIN gridwalk.layers l ON l.id = v.layer_id
+            WHERE v.layer_id = $1 AND l.workspace_id = $3
+            AND ($2::integer IS NULL OR v.version = $2)
+            ORDER BY v.version DESC
+            LIMIT 1",
+        )
+        .bind(layer_id)
+        .bind(version.map(|version| version as i32))
+        .bind(workspace_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch version of layer {}: {}", layer_id, e))?
+        .map(version_from_row)
+        .ok_or_else(|| match version {
+            Some(version) => anyhow!("Version {} of layer {} not found", version, layer_id),
+            None => anyhow!("Layer {} has no versions", layer_id),
+        })
+    }
+
+    async fn find_versions_by_checksum<'e, E>(
+        workspace_id: Uuid,
+        sha256: &str,
+        executor: E,
+    ) -> Result<Vec<LayerVersion>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM gridwalk.layer_versions v
+            JOIN gridwalk.layers l ON l.id = v.layer_id
+            WHERE l.workspace_id = $1 AND v.lineage->>'sha256' = $2
+            ORDER BY v.created_at DESC",
+        )
+        .bind(workspace_id)
+        .bind(sha256)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to find versions by checksum: {}", e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+
+    async fn prune_versions<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        keep: u32,
+        executor: E,
+    ) -> Result<Vec<LayerVersion>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        if keep == 0 {
+            return Err(anyhow!("At least one version must be kept"));
+        }
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "DELETE FROM gridwalk.layer_versions v
+            USING gridwalk.layers l
+            WHERE l.id = v.layer_id AND v.layer_id = $1 AND l.workspace_id = $3
+            AND v.version NOT IN (
+                SELECT version FROM gridwalk.layer_versions
+                WHERE layer_id = $1
+                ORDER BY version DESC
+                LIMIT $2
+            )
+            RETURNING v.layer_id, v.version, v.table_name, v.lineage, v.created_at",
+        )
+        .bind(layer_id)
+        .bind(keep as i64)
+        .bind(workspace_id)
+        .fetch_all(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to prune versions of layer {}: {}", layer_id, e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+}
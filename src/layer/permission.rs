@@ -0,0 +1,399 @@
+use crate::{LayerSource, ThumbnailOptions, TileOptions, VectorConnector};
+use anyhow::{Result, anyhow};
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+/// Access level on a layer or workspace; each level includes the ones below it
+#[derive(
+    Copy, Clone, Debug, Display, Serialize, Deserialize, EnumString, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Write,
+    Admin,
+}
+
+/// What a grant applies to: every layer in a workspace, or a single layer
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "id", rename_all = "lowercase")]
+pub enum PermissionScope {
+    Workspace(Uuid),
+    Layer(Uuid),
+}
+
+/// Permission given to a principal (user, team or API key) within a scope
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PermissionGrant {
+    pub principal_id: Uuid,
+    pub scope: PermissionScope,
+    pub permission: Permission,
+}
+
+impl PermissionGrant {
+    /// Highest permission among `grants`, `None` when there are none
+    pub fn effective(grants: &[PermissionGrant]) -> Option<Permission> {
+        grants.iter().map(|grant| grant.permission).max()
+    }
+}
+
+/// Storage of permission grants, implemented by host applications
+pub trait PermissionStore {
+    fn grant<'e, E>(
+        grant: &PermissionGrant,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<()>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Remove a principal's grant in a scope, returning `false` if there was none
+    fn revoke<'e, E>(
+        principal_id: Uuid,
+        scope: PermissionScope,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<bool>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Grants held by a principal on the workspace and, when given, on the layer itself
+    fn grants_for<'e, E>(
+        principal_id: Uuid,
+        workspace_id: Uuid,
+        layer_id: Option<Uuid>,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<PermissionGrant>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+}
+
+/// Connector guarded by the permission a principal holds on one layer. Operations are reached
+/// through the views returned by [`read`](Self::read), [`write`](Self::write) or
+/// [`admin`](Self::admin), which fail unless that level has been granted. Each view is bound
+/// to the checked layer's source, so it cannot be pointed at another layer.
+pub struct CheckedConnector<'a, C: VectorConnector + ?Sized> {
+    connector: &'a C,
+    source: &'a LayerSource,
+    permission: Option<Permission>,
+}
+
+impl<'a, C: VectorConnector + ?Sized> CheckedConnector<'a, C> {
+    pub fn new(connector: &'a C, source: &'a LayerSource, permission: Option<Permission>) -> Self {
+        CheckedConnector {
+            connector,
+            source,
+            permission,
+        }
+    }
+
+    /// Resolve the principal's effective permission on a layer from the store. `source` must
+    /// be the stored source of `layer_id`.
+    pub async fn for_principal<'e, P, E>(
+        connector: &'a C,
+        source: &'a LayerSource,
+        principal_id: Uuid,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        executor: E,
+    ) -> Result<Self>
+    where
+        P: PermissionStore,
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let grants = P::grants_for(principal_id, workspace_id, Some(layer_id), executor).await?;
+        Ok(Self::new(
+            connector,
+            source,
+            PermissionGrant::effective(&grants),
+        ))
+    }
+
+    /// Permission held on the layer, if any
+    pub fn permission(&self) -> Option<Permission> {
+        self.permission
+    }
+
+    /// Fail unless `required` (or a higher level) has been granted
+    pub fn require(&self, required: Permission) -> Result<()> {
+        match self.permission {
+            Some(permission) if permission >= required => Ok(()),
+            _ => Err(anyhow!("Permission denied: {} access required", required)),
+        }
+    }
+
+    /// Tiles, queries and exports of the layer
+    pub fn read(&self) -> Result<LayerReader<'a, C>> {
+        self.require(Permission::Read)?;
+        Ok(LayerReader {
+            connector: self.connector,
+            source: self.source,
+        })
+    }
+
+    /// Operations that write new data derived from the layer
+    pub fn write(&self) -> Result<LayerWriter<'a, C>> {
+        self.require(Permission::Write)?;
+        Ok(LayerWriter {
+            reader: self.read()?,
+        })
+    }
+
+    /// Structural operations such as renaming or dropping the layer
+    pub fn admin(&self) -> Result<LayerAdmin<'a, C>> {
+        self.require(Permission::Admin)?;
+        Ok(LayerAdmin {
+            writer: self.write()?,
+        })
+    }
+}
+
+/// Read access to one layer, from [`CheckedConnector::read`]
+pub struct LayerReader<'a, C: VectorConnector + ?Sized> {
+    connector: &'a C,
+    source: &'a LayerSource,
+}
+
+impl<'a, C: VectorConnector + ?Sized> LayerReader<'a, C> {
+    pub fn source(&self) -> &'a LayerSource {
+        self.source
+    }
+
+    pub async fn get_tile(&self, layer_name: &str, z: u32, x: u32, y: u32) -> Result<Vec<u8>> {
+        self.connector
+            .get_tile(self.source, layer_name, z, x, y)
+            .await
+    }
+
+    pub async fn get_tile_with_options(
+        &self,
+        layer_name: &str,
+        z: u32,
+        x: u32,
+        y: u32,
+        options: &TileOptions,
+    ) -> Result<Vec<u8>> {
+        self.connector
+            .get_tile_with_options(self.source, layer_name, z, x, y, options)
+            .await
+    }
+
+    pub async fn get_tile_hashed(
+        &self,
+        layer_name: &str,
+        z: u32,
+        x: u32,
+        y: u32,
+        options: &TileOptions,
+    ) -> Result<crate::Tile> {
+        self.connector
+            .get_tile_hashed(self.source, layer_name, z, x, y, options)
+            .await
+    }
+
+    pub async fn get_utfgrid(
+        &self,
+        z: u32,
+        x: u32,
+        y: u32,
+        key_field: &str,
+        grid: &crate::TileGrid,
+    ) -> Result<crate::UtfGrid> {
+        self.connector
+            .get_utfgrid(self.source, z, x, y, key_field, grid)
+            .await
+    }
+
+    pub async fn query_bbox(
+        &self,
+        bbox: &crate::Bbox,
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection> {
+        self.connector
+            .query_bbox(self.source, bbox, filter, page, output)
+            .await
+    }
+
+    pub async fn query_spatial(
+        &self,
+        geometry: &crate::QueryGeometry,
+        predicate: crate::SpatialPredicate,
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection> {
+        self.connector
+            .query_spatial(self.source, geometry, predicate, filter, page, output)
+            .await
+    }
+
+    pub async fn query_attributes(
+        &self,
+        fields: &[String],
+        filter: Option<&crate::Filter>,
+        page: crate::FeaturePage,
+    ) -> Result<crate::AttributeTable> {
+        self.connector
+            .query_attributes(self.source, fields, filter, page)
+            .await
+    }
+
+    pub async fn get_feature(
+        &self,
+        id: i64,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::GeoJsonFeature> {
+        self.connector.get_feature(self.source, id, output).await
+    }
+
+    pub async fn nearest(
+        &self,
+        point: geo_types::Point<f64>,
+        k: u32,
+        max_distance: Option<f64>,
+        output: crate::FeatureOutput,
+    ) -> Result<crate::FeatureCollection> {
+        self.connector
+            .nearest(self.source, point, k, max_distance, output)
+            .await
+    }
+
+    pub async fn field_stats(&self, field: &str, bins: u32) -> Result<crate::FieldStats> {
+        self.connector.field_stats(self.source, field, bins).await
+    }
+
+    pub async fn distinct_values(
+        &self,
+        field: &str,
+        limit: u32,
+    ) -> Result<Vec<crate::DistinctValue>> {
+        self.connector
+            .distinct_values(self.source, field, limit)
+            .await
+    }
+
+    pub async fn get_coordinate_dimension(&self) -> Result<crate::CoordinateDimension> {
+        self.connector.get_coordinate_dimension(self.source).await
+    }
+
+    pub async fn get_extent(&self, accurate: bool) -> Result<Option<crate::Bbox>> {
+        self.connector.get_extent(self.source, accurate).await
+    }
+
+    pub async fn count(&self, filter: Option<&crate::Filter>) -> Result<u64> {
+        self.connector.count(self.source, filter).await
+    }
+
+    pub async fn geometry_type_counts(&self) -> Result<Vec<crate::GeometryTypeCount>> {
+        self.connector.geometry_type_counts(self.source).await
+    }
+
+    pub async fn compute_layer_stats(&self, fields: &[String]) -> Result<crate::LayerStats> {
+        self.connector
+            .compute_layer_stats(self.source, fields)
+            .await
+    }
+
+    pub async fn render_thumbnail(&self, options: &ThumbnailOptions) -> Result<Vec<u8>> {
+        self.connector.render_thumbnail(self.source, options).await
+    }
+
+    pub fn export_geojson<'b>(
+        &'b self,
+        filter: Option<&'b crate::Filter>,
+        output_srid: Option<i32>,
+    ) -> BoxStream<'b, Result<bytes::Bytes>> {
+        self.connector
+            .export_geojson(self.source, filter, output_srid)
+    }
+
+    pub fn export_ndjson<'b>(
+        &'b self,
+        filter: Option<&'b crate::Filter>,
+        output_srid: Option<i32>,
+    ) -> BoxStream<'b, Result<bytes::Bytes>> {
+        self.connector
+            .export_ndjson(self.source, filter, output_srid)
+    }
+
+    /// Join against another layer, which needs read access of its own
+    pub fn spatial_join<'b>(
+        &'b self,
+        right: &'b LayerReader<'a, C>,
+        predicate: crate::SpatialPredicate,
+        fields: &'b [String],
+        output: crate::FeatureOutput,
+    ) -> BoxStream<'b, Result<crate::GeoJsonFeature>> {
+        self.connector
+            .spatial_join(self.source, right.source, predicate, fields, output)
+    }
+}
+
+/// Write access to one layer, from [`CheckedConnector::write`]; also reads
+pub struct LayerWriter<'a, C: VectorConnector + ?Sized> {
+    reader: LayerReader<'a, C>,
+}
+
+impl<'a, C: VectorConnector + ?Sized> LayerWriter<'a, C> {
+    pub fn reader(&self) -> &LayerReader<'a, C> {
+        &self.reader
+    }
+
+    /// Copy the layer's data to `new_name` in the same namespace
+    pub async fn copy_layer(&self, new_name: &str) -> Result<LayerSource> {
+        self.reader
+            .connector
+            .copy_layer(self.reader.source, new_name)
+            .await
+    }
+
+    /// Write the join against another readable layer to `target_name` in this layer's
+    /// namespace
+    pub async fn materialize_spatial_join(
+        &self,
+        right: &LayerReader<'a, C>,
+        predicate: crate::SpatialPredicate,
+        fields: &[String],
+        target_name: &str,
+    ) -> Result<LayerSource> {
+        self.reader
+            .connector
+            .materialize_spatial_join(
+                self.reader.source,
+                right.source,
+                predicate,
+                fields,
+                target_name,
+            )
+            .await
+    }
+}
+
+/// Full control of one layer, from [`CheckedConnector::admin`]; also reads and writes
+pub struct LayerAdmin<'a, C: VectorConnector + ?Sized> {
+    writer: LayerWriter<'a, C>,
+}
+
+impl<'a, C: VectorConnector + ?Sized> LayerAdmin<'a, C> {
+    pub fn writer(&self) -> &LayerWriter<'a, C> {
+        &self.writer
+    }
+
+    pub fn reader(&self) -> &LayerReader<'a, C> {
+        &self.writer.reader
+    }
+
+    /// Rename the layer's backing table, returning its new source
+    pub async fn rename_layer(&self, new_name: &str) -> Result<LayerSource> {
+        let reader = &self.writer.reader;
+        reader.connector.rename_layer(reader.source, new_name).await
+    }
+
+    /// Drop the layer's backing table and all of its data
+    pub async fn drop_layer(&self) -> Result<()> {
+        let reader = &self.writer.reader;
+        reader.connector.drop_layer(reader.source).await
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Style body in one of the supported formats
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "format", content = "body", rename_all = "snake_case")]
+pub enum StyleDefinition {
+    /// Mapbox GL / MapLibre style snippet, typically a list of style layers
+    MapboxGl(serde_json::Value),
+    /// OGC Styled Layer Descriptor XML
+    Sld(String),
+}
+
+/// A saved version of a layer's style
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LayerStyle {
+    pub layer_id: Uuid,
+    /// Incremented on every save, starting at 1
+    pub version: u32,
+    pub definition: StyleDefinition,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Storage of layer styles, implemented by host applications. Saving never overwrites: each
+/// save adds a new version, so earlier styles can be restored.
+pub trait LayerStyleStore {
+    /// Store `definition` as the next version of the layer's style
+    fn save_style<'e, E>(
+        layer_id: Uuid,
+        definition: &StyleDefinition,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<LayerStyle>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// A specific style version, or the latest when `version` is `None`. Returns `None` for a
+    /// layer without a style.
+    fn get_style<'e, E>(
+        layer_id: Uuid,
+        version: Option<u32>,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Option<LayerStyle>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Every style version of a layer, newest first
+    fn list_styles<'e, E>(
+        layer_id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerStyle>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+}
@@ -0,0 +1,283 @@
+use super::LayerCore;
+use crate::metadata::MetadataStore;
+use crate::{ConnectorBase, LayerSource};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Streaming SHA-256 and xxh3 hashes of a file, fed chunk by chunk as an upload arrives so the
+/// file need not be read again afterwards
+#[derive(Clone, Default)]
+pub struct ContentHasher {
+    sha256: Sha256,
+    xxh3: xxhash_rust::xxh3::Xxh3,
+    size: u64,
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.sha256.update(chunk);
+        self.xxh3.update(chunk);
+        self.size += chunk.len() as u64;
+    }
+
+    pub fn finish(self) -> ContentHash {
+        ContentHash {
+            size: self.size,
+            sha256: format!("{:x}", self.sha256.finalize()),
+            xxh3: format!("{:032x}", self.xxh3.digest128()),
+        }
+    }
+
+    /// Hash a whole file. Blocking, so call it from `spawn_blocking` in async code.
+    pub fn hash_file(path: &Path) -> Result<ContentHash> {
+        let mut file = std::fs::File::open(path)
+            .map_err(|e| anyhow!("Failed to open '{}': {}", path.display(), e))?;
+        let mut hasher = ContentHasher::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| anyhow!("Failed to read '{}': {}", path.display(), e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+/// Hashes of a file's content, from [`ContentHasher`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentHash {
+    /// File size in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256 checksum, for integrity verification
+    pub sha256: String,
+    /// Hex-encoded 128-bit xxh3 hash, a fast key for duplicate detection
+    pub xxh3: String,
+}
+
+/// Where a layer version was built from, so users can verify exactly which file was ingested
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LayerLineage {
+    /// Original filename as uploaded
+    pub filename: String,
+    /// File size in bytes
+    pub file_size: u64,
+    /// Hex-encoded SHA-256 checksum of the file
+    pub sha256: String,
+    /// Hex-encoded xxh3 hash of the file, absent for lineage recorded before it was computed
+    #[serde(default)]
+    pub xxh3: Option<String>,
+    /// Short name of the GDAL driver that read the file, e.g. `GPKG`
+    pub driver: String,
+    /// Options the ingest was run with
+    #[serde(default)]
+    pub options: serde_json::Value,
+}
+
+impl LayerLineage {
+    /// Checksum a file and detect its driver. `filename` is the name the user uploaded, which
+    /// may differ from the temporary file at `path`.
+    pub async fn from_file(
+        path: impl AsRef<Path>,
+        filename: &str,
+        options: serde_json::Value,
+    ) -> Result<Self> {
+        Self::build(path.as_ref(), filename, None, options).await
+    }
+
+    /// As [`from_file`](Self::from_file), with hashes already computed by a [`ContentHasher`]
+    /// while the file was uploaded
+    pub async fn from_upload(
+        path: impl AsRef<Path>,
+        filename: &str,
+        hash: ContentHash,
+        options: serde_json::Value,
+    ) -> Result<Self> {
+        Self::build(path.as_ref(), filename, Some(hash), options).await
+    }
+
+    async fn build(
+        path: &Path,
+        filename: &str,
+        hash: Option<ContentHash>,
+        options: serde_json::Value,
+    ) -> Result<Self> {
+        let path = path.to_path_buf();
+        let filename = filename.to_string();
+
+        // Hashing and GDAL are blocking, so both run off the async runtime
+        tokio::task::spawn_blocking(move || {
+            let driver = gdal::Dataset::open(&path)
+                .map_err(|e| anyhow!("Failed to open '{}': {}", path.display(), e))?
+                .driver()
+                .short_name();
+
+            let hash = match hash {
+                Some(hash) => hash,
+                None => ContentHasher::hash_file(&path)?,
+            };
+
+            Ok(LayerLineage {
+                filename,
+                file_size: hash.size,
+                sha256: hash.sha256,
+                xxh3: Some(hash.xxh3),
+                driver,
+                options,
+            })
+        })
+        .await
+        .map_err(|e| anyhow!("Lineage task failed: {}", e))?
+    }
+
+    /// Whether the file at `path` still matches the recorded size and SHA-256 checksum
+    pub async fn verify(&self, path: impl AsRef<Path>) -> Result<bool> {
+        let path = path.as_ref().to_path_buf();
+        let hash = tokio::task::spawn_blocking(move || ContentHasher::hash_file(&path))
+            .await
+            .map_err(|e| anyhow!("Checksum task failed: {}", e))??;
+        Ok(hash.size == self.file_size && hash.sha256 == self.sha256)
+    }
+}
+
+/// Longest identifier Postgres keeps without truncating it, in bytes
+const MAX_IDENTIFIER_BYTES: usize = 63;
+
+/// One ingest of a layer, stored in its own table so older versions stay readable
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LayerVersion {
+    pub layer_id: Uuid,
+    pub version: u32,
+    /// Table holding this version's data
+    pub table_name: String,
+    /// Source file the version was ingested from, if recorded
+    #[serde(default)]
+    pub lineage: Option<LayerLineage>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl LayerVersion {
+    /// Table name `create_version` records for `version` of a layer stored in `table`:
+    /// `<table>_v<version>`, with `table` cut at a character boundary so the whole name fits
+    /// Postgres's 63-byte identifier limit
+    pub fn table_name_for(table: &str, version: u32) -> String {
+        let suffix = format!("_v{}", version);
+        let mut end = table.len().min(MAX_IDENTIFIER_BYTES - suffix.len());
+        while !table.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}{}", &table[..end], suffix)
+    }
+
+    /// Source pinned to this version: `base` with its table swapped for the version's table.
+    /// Pass it to `get_tile` and the query methods to read the layer at this point in time.
+    pub fn source(&self, base: &LayerSource) -> LayerSource {
+        match base {
+            LayerSource::Database {
+                namespace,
+                geometry_field,
+                srid,
+                ..
+            } => LayerSource::Database {
+                namespace: namespace.clone(),
+                name: self.table_name.clone(),
+                geometry_field: geometry_field.clone(),
+                srid: *srid,
+            },
+        }
+    }
+
+    /// Version tag for `TileOptions::layer_version`, so tile hashes change between versions
+    pub fn tag(&self) -> String {
+        format!("v{}", self.version)
+    }
+}
+
+/// Version history of layers, implemented alongside [`LayerCore`]. Every method is scoped to
+/// `workspace_id` and treats a layer of another workspace as missing.
+pub trait LayerVersionStore: LayerCore {
+    /// Record a new version with the next version number and the file it is built from, and
+    /// return it; data is then ingested into its `table_name`, named after the layer's source
+    /// table by [`LayerVersion::table_name_for`]. Fails for layers without a source.
+    fn create_version<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        lineage: Option<&LayerLineage>,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<LayerVersion>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Every version of a layer, newest first
+    fn list_versions<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerVersion>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// A specific version, or the latest when `version` is `None`
+    fn get_version<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        version: Option<u32>,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<LayerVersion>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Versions in a workspace ingested from a file with this SHA-256 checksum, newest first, to
+    /// spot an upload that duplicates an existing layer
+    fn find_versions_by_checksum<'e, E>(
+        workspace_id: Uuid,
+        sha256: &str,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerVersion>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+
+    /// Remove all but the newest `keep` version records, returning the removed versions. Fails
+    /// when `keep` is 0, so the newest version is never removed.
+    fn prune_versions<'e, E>(
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        keep: u32,
+        executor: E,
+    ) -> impl std::future::Future<Output = Result<Vec<LayerVersion>>> + Send
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>;
+}
+
+/// Prune old versions of a layer, keeping the newest `keep`, and drop their tables through the
+/// connector. `base` is the layer's source, used to locate each version's table. `keep` must be
+/// at least 1, and a version whose table `base` itself points at keeps its table.
+pub async fn prune_layer_versions<C>(
+    workspace_id: Uuid,
+    layer_id: Uuid,
+    keep: u32,
+    base: &LayerSource,
+    connector: &C,
+    store: &dyn MetadataStore,
+) -> Result<Vec<LayerVersion>>
+where
+    C: ConnectorBase + ?Sized,
+{
+    let pruned = store.prune_versions(workspace_id, layer_id, keep).await?;
+    let LayerSource::Database { name, .. } = base;
+    for version in pruned.iter().filter(|version| version.table_name != *name) {
+        connector.drop_layer(&version.source(base)).await?;
+    }
+    Ok(pruned)
+}
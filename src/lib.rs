@@ -3,6 +3,9 @@ pub mod conversion;
 pub mod file;
 pub mod file_utils;
 mod layer;
+pub mod metadata;
+pub mod migrations;
 
 pub use connector::*;
 pub use layer::*;
+pub use metadata::*;
@@ -0,0 +1,9 @@
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod store;
+
+pub use postgres::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+pub use store::*;
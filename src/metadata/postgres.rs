@@ -0,0 +1,305 @@
+use super::MetadataStore;
+use crate::conversion::FeatureChunk;
+use crate::file::LayerSchema;
+use crate::{
+    ChunkJob, DeletedLayer, INGEST_CHUNK_JOB, Layer, LayerCore, LayerLineage, LayerQuery,
+    LayerSource, LayerSummary, LayerUpdate, LayerVersion, LayerVersionStore, Page,
+};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use sqlx::PgPool;
+use sqlx::types::Json;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// [`MetadataStore`] backed by the `gridwalk` schema in Postgres, delegating to the
+/// [`LayerCore`] and [`LayerVersionStore`] implementations of [`Layer`]. Chunk jobs are
+/// completed in the transaction that inserts the chunk's rows, so the `gridwalk` schema must
+/// live in the connector's database when ingesting in chunks.
+#[derive(Clone, Debug)]
+pub struct PostgresMetadataStore {
+    pool: PgPool,
+}
+
+impl PostgresMetadataStore {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresMetadataStore { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl MetadataStore for PostgresMetadataStore {
+    async fn initialize(&self) -> Result<()> {
+        crate::migrations::initialize(&self.pool).await
+    }
+
+    async fn save(&self, layer: &Layer) -> Result<()> {
+        layer.save(&self.pool).await
+    }
+
+    async fn list(
+        &self,
+        workspace_id: Uuid,
+        query: &LayerQuery,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Page<LayerSummary>> {
+        Layer::list(workspace_id, query, limit, offset, &self.pool).await
+    }
+
+    async fn get(&self, workspace_id: Uuid, id: Uuid) -> Result<Layer> {
+        Layer::get(workspace_id, id, &self.pool).await
+    }
+
+    async fn exists(&self, workspace_id: Uuid, id: Uuid) -> Result<bool> {
+        Layer::exists(workspace_id, id, &self.pool).await
+    }
+
+    async fn schema(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<LayerSchema>> {
+        Layer::schema(workspace_id, id, &self.pool).await
+    }
+
+    async fn thumbnail(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<Vec<u8>>> {
+        Layer::thumbnail(workspace_id, id, &self.pool).await
+    }
+
+    async fn duplicate(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        new_name: &str,
+        source: &LayerSource,
+    ) -> Result<Layer> {
+        Layer::duplicate(workspace_id, id, new_name, source, &self.pool).await
+    }
+
+    async fn expired(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<LayerSummary>> {
+        Layer::expired(now, &self.pool).await
+    }
+
+    async fn update(&self, workspace_id: Uuid, id: Uuid, changes: &LayerUpdate) -> Result<Layer> {
+        Layer::update(workspace_id, id, changes, &self.pool).await
+    }
+
+    async fn delete(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<DeletedLayer>> {
+        Layer::delete(workspace_id, id, &self.pool).await
+    }
+
+    async fn delete_many(&self, workspace_id: Uuid, ids: &[Uuid]) -> Result<Vec<DeletedLayer>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let mut deleted = Vec::with_capacity(ids.len());
+        for id in ids {
+            deleted.extend(Layer::delete(workspace_id, *id, &mut *tx).await?);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit layer deletion: {}", e))?;
+        Ok(deleted)
+    }
+
+    async fn update_many(
+        &self,
+        workspace_id: Uuid,
+        updates: &[(Uuid, LayerUpdate)],
+    ) -> Result<Vec<Layer>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let mut updated = Vec::with_capacity(updates.len());
+        for (id, changes) in updates {
+            updated.push(Layer::update(workspace_id, *id, changes, &mut *tx).await?);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit layer updates: {}", e))?;
+        Ok(updated)
+    }
+
+    async fn create_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        lineage: Option<&LayerLineage>,
+    ) -> Result<LayerVersion> {
+        Layer::create_version(workspace_id, layer_id, lineage, &self.pool).await
+    }
+
+    async fn list_versions(&self, workspace_id: Uuid, layer_id: Uuid) -> Result<Vec<LayerVersion>> {
+        Layer::list_versions(workspace_id, layer_id, &self.pool).await
+    }
+
+    async fn get_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        version: Option<u32>,
+    ) -> Result<LayerVersion> {
+        Layer::get_version(workspace_id, layer_id, version, &self.pool).await
+    }
+
+    async fn find_versions_by_checksum(
+        &self,
+        workspace_id: Uuid,
+        sha256: &str,
+    ) -> Result<Vec<LayerVersion>> {
+        Layer::find_versions_by_checksum(workspace_id, sha256, &self.pool).await
+    }
+
+    async fn prune_versions(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        keep: u32,
+    ) -> Result<Vec<LayerVersion>> {
+        Layer::prune_versions(workspace_id, layer_id, keep, &self.pool).await
+    }
+
+    async fn queue_chunk_jobs(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        chunks: &[FeatureChunk],
+    ) -> Result<Vec<ChunkJob>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        for chunk in chunks {
+            sqlx::query(
+                "INSERT INTO gridwalk.jobs (id, workspace_id, layer_id, kind, status, payload)
+                VALUES ($1, $2, $3, $4, 'pending', $5)
+                ON CONFLICT (layer_id, kind, (payload->>'index')) DO NOTHING",
+            )
+            .bind(Uuid::new_v4())
+            .bind(workspace_id)
+            .bind(layer_id)
+            .bind(INGEST_CHUNK_JOB)
+            .bind(Json(chunk))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to queue chunk {}: {}", chunk.index, e))?;
+        }
+
+        let pending: Vec<(Uuid, Json<FeatureChunk>)> = sqlx::query_as(
+            "SELECT j.id, j.payload
+            FROM gridwalk.jobs j
+            JOIN gridwalk.layers l ON l.id = j.layer_id
+            WHERE j.layer_id = $1 AND l.workspace_id = $2 AND j.kind = $3
+            AND j.status <> 'completed'
+            ORDER BY (j.payload->>'index')::bigint",
+        )
+        .bind(layer_id)
+        .bind(workspace_id)
+        .bind(INGEST_CHUNK_JOB)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to read chunk jobs of layer {}: {}", layer_id, e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit chunk jobs: {}", e))?;
+
+        Ok(pending
+            .into_iter()
+            .map(|(id, chunk)| ChunkJob {
+                id,
+                layer_id,
+                chunk: chunk.0,
+            })
+            .collect())
+    }
+
+    async fn claim_chunk_job(&self, job_id: Uuid, lease: Duration) -> Result<bool> {
+        let claimed = sqlx::query(
+            "UPDATE gridwalk.jobs
+            SET status = 'running', attempts = attempts + 1, updated_at = now()
+            WHERE id = (
+                SELECT id FROM gridwalk.jobs
+                WHERE id = $1
+                AND (
+                    status IN ('pending', 'failed')
+                    OR (status = 'running' AND updated_at < now() - make_interval(secs => $2))
+                )
+                FOR UPDATE SKIP LOCKED
+            )",
+        )
+        .bind(job_id)
+        .bind(lease.as_secs_f64())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to claim job {}: {}", job_id, e))?;
+        Ok(claimed.rows_affected() > 0)
+    }
+
+    async fn complete_chunk_job_with(
+        &self,
+        job_id: Uuid,
+        rows: &mut sqlx::PgConnection,
+    ) -> Result<Option<bool>> {
+        // A worker that reclaimed the job after its lease expired may have finished it
+        // meanwhile; the row lock makes the two commits take turns so only one keeps its rows
+        let (status,): (String,) =
+            sqlx::query_as("SELECT status FROM gridwalk.jobs WHERE id = $1 FOR UPDATE")
+                .bind(job_id)
+                .fetch_one(&mut *rows)
+                .await
+                .map_err(|e| anyhow!("Failed to lock job {}: {}", job_id, e))?;
+        if status == "completed" {
+            return Ok(Some(false));
+        }
+
+        sqlx::query(
+            "UPDATE gridwalk.jobs
+            SET status = 'completed', error = NULL, updated_at = now()
+            WHERE id = $1",
+        )
+        .bind(job_id)
+        .execute(&mut *rows)
+        .await
+        .map_err(|e| anyhow!("Failed to complete job {}: {}", job_id, e))?;
+        Ok(Some(true))
+    }
+
+    async fn complete_chunk_job(&self, job_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE gridwalk.jobs
+            SET status = 'completed', error = NULL, updated_at = now()
+            WHERE id = $1",
+        )
+        .bind(job_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to complete job {}: {}", job_id, e))?;
+        Ok(())
+    }
+
+    async fn fail_chunk_job(&self, job_id: Uuid, error: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE gridwalk.jobs
+            SET status = 'failed', error = $2, updated_at = now()
+            WHERE id = $1",
+        )
+        .bind(job_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to record failure of job {}: {}", job_id, e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,821 @@
+use super::MetadataStore;
+use crate::conversion::FeatureChunk;
+use crate::file::LayerSchema;
+use crate::{
+    ChunkJob, DeletedLayer, INGEST_CHUNK_JOB, Layer, LayerLineage, LayerQuery, LayerSource,
+    LayerStats, LayerStatus, LayerStatusTransition, LayerSummary, LayerUpdate, LayerVersion, Page,
+};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use sqlx::types::Json;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Columns of the SQLite `layers` table read into a [`SqliteLayerRow`]
+const LAYER_COLUMNS: &str = "id, workspace_id, owner_id, name, description, status, \
+    status_history, progress, status_message, connector_id, tags, metadata, stats, schema, \
+    source, expires_at, created_at, updated_at";
+
+/// Same layout as `gridwalk.layers` in Postgres, with JSON and array columns stored as text
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS layers (
+        id BLOB PRIMARY KEY,
+        workspace_id BLOB NOT NULL,
+        owner_id BLOB,
+        name TEXT NOT NULL,
+        description TEXT,
+        status TEXT NOT NULL,
+        status_history TEXT NOT NULL DEFAULT '[]',
+        progress REAL,
+        status_message TEXT,
+        connector_id TEXT,
+        tags TEXT NOT NULL DEFAULT '[]',
+        metadata TEXT NOT NULL DEFAULT '{}',
+        stats TEXT,
+        schema TEXT,
+        thumbnail BLOB,
+        source TEXT,
+        expires_at TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS layers_workspace_created_idx
+        ON layers (workspace_id, created_at);
+    CREATE INDEX IF NOT EXISTS layers_expires_at_idx
+        ON layers (expires_at) WHERE expires_at IS NOT NULL;
+    CREATE TABLE IF NOT EXISTS layer_versions (
+        layer_id BLOB NOT NULL REFERENCES layers (id) ON DELETE CASCADE,
+        version INTEGER NOT NULL,
+        table_name TEXT NOT NULL,
+        lineage TEXT,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (layer_id, version)
+    );
+    CREATE INDEX IF NOT EXISTS layer_versions_sha256_idx
+        ON layer_versions (json_extract(lineage, '$.sha256')) WHERE lineage IS NOT NULL;
+    CREATE TABLE IF NOT EXISTS jobs (
+        id BLOB PRIMARY KEY,
+        workspace_id BLOB NOT NULL,
+        layer_id BLOB REFERENCES layers (id) ON DELETE CASCADE,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        payload TEXT NOT NULL DEFAULT '{}',
+        attempts INTEGER NOT NULL DEFAULT 0,
+        error TEXT,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS jobs_layer_kind_index_idx
+        ON jobs (layer_id, kind, json_extract(payload, '$.index'));
+";
+
+/// Columns of the SQLite `layer_versions` table as a [`LayerVersion`] tuple
+type VersionRow = (
+    Uuid,
+    i64,
+    String,
+    Option<Json<LayerLineage>>,
+    chrono::DateTime<chrono::Utc>,
+);
+
+fn version_from_row(
+    (layer_id, version, table_name, lineage, created_at): VersionRow,
+) -> LayerVersion {
+    LayerVersion {
+        layer_id,
+        version: version as u32,
+        table_name,
+        lineage: lineage.map(|lineage| lineage.0),
+        created_at,
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SqliteLayerRow {
+    id: Uuid,
+    workspace_id: Uuid,
+    owner_id: Option<Uuid>,
+    name: String,
+    description: Option<String>,
+    status: String,
+    status_history: Json<Vec<LayerStatusTransition>>,
+    progress: Option<f32>,
+    status_message: Option<String>,
+    connector_id: Option<String>,
+    tags: Json<Vec<String>>,
+    metadata: Json<serde_json::Value>,
+    stats: Option<Json<LayerStats>>,
+    schema: Option<Json<LayerSchema>>,
+    source: Option<Json<LayerSource>>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TryFrom<SqliteLayerRow> for Layer {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteLayerRow) -> Result<Self> {
+        Ok(Layer {
+            id: row.id,
+            workspace_id: row.workspace_id,
+            owner_id: row.owner_id,
+            name: row.name,
+            description: row.description,
+            status: LayerStatus::from_str(&row.status)
+                .map_err(|_| anyhow!("Unknown layer status '{}'", row.status))?,
+            status_history: row.status_history.0,
+            progress: row.progress,
+            status_message: row.status_message,
+            connector_id: row.connector_id,
+            tags: row.tags.0,
+            metadata: row.metadata.0,
+            stats: row.stats.map(|stats| stats.0),
+            schema: row.schema.map(|schema| schema.0),
+            source: row.source.map(|source| source.0),
+            expires_at: row.expires_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+/// SQLite equivalent of [`LayerQuery::push_conditions`]. LIKE is case-insensitive for ASCII
+/// in SQLite, and tags are matched against the JSON array one at a time.
+fn push_conditions(query: &LayerQuery, builder: &mut QueryBuilder<'_, Sqlite>) {
+    if let Some(name_contains) = &query.name_contains {
+        let escaped = name_contains
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        builder.push(" AND name LIKE ");
+        builder.push_bind(format!("%{}%", escaped));
+        builder.push(" ESCAPE '\\'");
+    }
+    if let Some(status) = &query.status {
+        builder.push(" AND status = ");
+        builder.push_bind(status.to_string());
+    }
+    if let Some(owner_id) = query.owner_id {
+        builder.push(" AND owner_id = ");
+        builder.push_bind(owner_id);
+    }
+    for tag in &query.tags {
+        builder.push(" AND EXISTS (SELECT 1 FROM json_each(layers.tags) WHERE json_each.value = ");
+        builder.push_bind(tag.clone());
+        builder.push(")");
+    }
+    if let Some(created_after) = query.created_after {
+        builder.push(" AND created_at > ");
+        builder.push_bind(created_after);
+    }
+}
+
+/// Shared by `update` and `update_many`, which runs it inside a transaction
+async fn update_layer<'e, E>(
+    workspace_id: Uuid,
+    id: Uuid,
+    changes: &LayerUpdate,
+    executor: E,
+) -> Result<Layer>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let mut builder = QueryBuilder::<Sqlite>::new("UPDATE layers SET updated_at = ");
+    builder.push_bind(chrono::Utc::now());
+    if let Some(name) = &changes.name {
+        builder.push(", name = ");
+        builder.push_bind(name.clone());
+    }
+    if let Some(description) = &changes.description {
+        builder.push(", description = ");
+        builder.push_bind(description.clone());
+    }
+    if let Some(transition) = &changes.transition {
+        builder.push(", status = ");
+        builder.push_bind(transition.to.to_string());
+        builder.push(", status_history = json_insert(status_history, '$[#]', json(");
+        builder.push_bind(Json(transition.clone()));
+        builder.push("))");
+    }
+    if let Some(progress) = changes.progress {
+        builder.push(", progress = ");
+        builder.push_bind(progress);
+    }
+    if let Some(status_message) = &changes.status_message {
+        builder.push(", status_message = ");
+        builder.push_bind(status_message.clone());
+    }
+    if let Some(tags) = &changes.tags {
+        builder.push(", tags = ");
+        builder.push_bind(Json(tags.clone()));
+    }
+    if let Some(metadata) = &changes.metadata {
+        builder.push(", metadata = ");
+        builder.push_bind(Json(metadata.clone()));
+    }
+    if let Some(stats) = &changes.stats {
+        builder.push(", stats = ");
+        builder.push_bind(Json(stats.clone()));
+    }
+    if let Some(schema) = &changes.schema {
+        builder.push(", schema = ");
+        builder.push_bind(Json(schema.clone()));
+    }
+    if let Some(thumbnail) = &changes.thumbnail {
+        builder.push(", thumbnail = ");
+        builder.push_bind(thumbnail.clone());
+    }
+    if let Some(source) = &changes.source {
+        builder.push(", source = ");
+        builder.push_bind(Json(source.clone()));
+    }
+    if let Some(expires_at) = changes.expires_at {
+        builder.push(", expires_at = ");
+        builder.push_bind(expires_at);
+    }
+    builder.push(" WHERE workspace_id = ");
+    builder.push_bind(workspace_id);
+    builder.push(" AND id = ");
+    builder.push_bind(id);
+    if let Some(transition) = &changes.transition {
+        builder.push(" AND status = ");
+        builder.push_bind(transition.from.to_string());
+    }
+    builder.push(format!(" RETURNING {}", LAYER_COLUMNS));
+
+    builder
+        .build_query_as::<SqliteLayerRow>()
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| anyhow!("Failed to update layer {}: {}", id, e))?
+        .ok_or_else(|| match &changes.transition {
+            Some(transition) => anyhow!(
+                "Layer {} not found or no longer in status {}",
+                id,
+                transition.from
+            ),
+            None => anyhow!("Layer {} not found", id),
+        })?
+        .try_into()
+}
+
+/// Shared by `delete` and `delete_many`, which runs it inside a transaction
+async fn delete_layer<'e, E>(
+    workspace_id: Uuid,
+    id: Uuid,
+    executor: E,
+) -> Result<Option<DeletedLayer>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let deleted: Option<(Option<Json<LayerSource>>,)> =
+        sqlx::query_as("DELETE FROM layers WHERE workspace_id = ? AND id = ? RETURNING source")
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| anyhow!("Failed to delete layer {}: {}", id, e))?;
+    Ok(deleted.map(|(source,)| DeletedLayer {
+        id,
+        source: source.map(|source| source.0),
+    }))
+}
+
+/// [`MetadataStore`] in a SQLite database, for running gridwalk-core embedded without a
+/// Postgres metadata database. Timestamps are stored as RFC 3339 text in UTC, which sorts
+/// chronologically. Only available with the `sqlite` feature.
+#[derive(Clone, Debug)]
+pub struct SqliteMetadataStore {
+    pool: SqlitePool,
+}
+
+impl SqliteMetadataStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteMetadataStore { pool }
+    }
+
+    /// Open (creating if needed) the database at `url`, e.g. `sqlite://gridwalk.db` or
+    /// `sqlite::memory:`
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options = sqlx::sqlite::SqliteConnectOptions::from_str(url)
+            .map_err(|e| anyhow!("Invalid SQLite URL: {}", e))?
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .map_err(|e| anyhow!("Failed to open SQLite metadata database: {}", e))?;
+        Ok(SqliteMetadataStore { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl MetadataStore for SqliteMetadataStore {
+    async fn initialize(&self) -> Result<()> {
+        sqlx::raw_sql(CREATE_TABLES)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to create metadata tables: {}", e))?;
+        Ok(())
+    }
+
+    async fn save(&self, layer: &Layer) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO layers (
+                id, workspace_id, owner_id, name, description, status, status_history, progress,
+                status_message, connector_id, tags, metadata, stats, schema, source, expires_at,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                owner_id = excluded.owner_id,
+                name = excluded.name,
+                description = excluded.description,
+                status = excluded.status,
+                status_history = excluded.status_history,
+                progress = excluded.progress,
+                status_message = excluded.status_message,
+                connector_id = excluded.connector_id,
+                tags = excluded.tags,
+                metadata = excluded.metadata,
+                stats = excluded.stats,
+                schema = excluded.schema,
+                source = excluded.source,
+                expires_at = excluded.expires_at,
+                updated_at = excluded.updated_at
+            WHERE layers.workspace_id = excluded.workspace_id",
+        )
+        .bind(layer.id)
+        .bind(layer.workspace_id)
+        .bind(layer.owner_id)
+        .bind(&layer.name)
+        .bind(&layer.description)
+        .bind(layer.status.to_string())
+        .bind(Json(&layer.status_history))
+        .bind(layer.progress)
+        .bind(&layer.status_message)
+        .bind(&layer.connector_id)
+        .bind(Json(&layer.tags))
+        .bind(Json(&layer.metadata))
+        .bind(layer.stats.as_ref().map(Json))
+        .bind(layer.schema.as_ref().map(Json))
+        .bind(layer.source.as_ref().map(Json))
+        .bind(layer.expires_at)
+        .bind(layer.created_at)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to save layer '{}': {}", layer.name, e))?;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        workspace_id: Uuid,
+        query: &LayerQuery,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Page<LayerSummary>> {
+        let mut count_builder =
+            QueryBuilder::<Sqlite>::new("SELECT count(*) FROM layers WHERE workspace_id = ");
+        count_builder.push_bind(workspace_id);
+        push_conditions(query, &mut count_builder);
+        let (total,) = count_builder
+            .build_query_as::<(i64,)>()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to count layers: {}", e))?;
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM layers WHERE workspace_id = ",
+            LAYER_COLUMNS
+        ));
+        builder.push_bind(workspace_id);
+        push_conditions(query, &mut builder);
+        builder.push(query.order_by());
+        builder.push(" LIMIT ");
+        builder.push_bind(limit as i64);
+        builder.push(" OFFSET ");
+        builder.push_bind(offset as i64);
+
+        let rows = builder
+            .build_query_as::<SqliteLayerRow>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to list layers: {}", e))?;
+        let items = rows
+            .into_iter()
+            .map(|row| Layer::try_from(row).map(|layer| layer.summary()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Page::new(items, total as u64, limit, offset))
+    }
+
+    async fn get(&self, workspace_id: Uuid, id: Uuid) -> Result<Layer> {
+        let query = format!(
+            "SELECT {} FROM layers WHERE workspace_id = ? AND id = ?",
+            LAYER_COLUMNS
+        );
+        sqlx::query_as::<_, SqliteLayerRow>(&query)
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch layer {}: {}", id, e))?
+            .ok_or_else(|| anyhow!("Layer {} not found", id))?
+            .try_into()
+    }
+
+    async fn exists(&self, workspace_id: Uuid, id: Uuid) -> Result<bool> {
+        let (exists,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM layers WHERE workspace_id = ? AND id = ?)",
+        )
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to check layer {}: {}", id, e))?;
+        Ok(exists)
+    }
+
+    async fn schema(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<LayerSchema>> {
+        let (schema,): (Option<Json<LayerSchema>>,) =
+            sqlx::query_as("SELECT schema FROM layers WHERE workspace_id = ? AND id = ?")
+                .bind(workspace_id)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| anyhow!("Failed to fetch schema of layer {}: {}", id, e))?
+                .ok_or_else(|| anyhow!("Layer {} not found", id))?;
+        Ok(schema.map(|schema| schema.0))
+    }
+
+    async fn thumbnail(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<Vec<u8>>> {
+        let (thumbnail,): (Option<Vec<u8>>,) =
+            sqlx::query_as("SELECT thumbnail FROM layers WHERE workspace_id = ? AND id = ?")
+                .bind(workspace_id)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| anyhow!("Failed to fetch thumbnail of layer {}: {}", id, e))?
+                .ok_or_else(|| anyhow!("Layer {} not found", id))?;
+        Ok(thumbnail)
+    }
+
+    async fn duplicate(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        new_name: &str,
+        source: &LayerSource,
+    ) -> Result<Layer> {
+        let now = chrono::Utc::now();
+        let query = format!(
+            "INSERT INTO layers (
+                id, workspace_id, owner_id, name, description, status, status_history, progress,
+                status_message, connector_id, tags, metadata, stats, schema, thumbnail, source,
+                expires_at, created_at, updated_at
+            )
+            SELECT ?, workspace_id, owner_id, ?, description, status, '[]', progress,
+                status_message, connector_id, tags, metadata, stats, schema, thumbnail, ?,
+                expires_at, ?, ?
+            FROM layers
+            WHERE workspace_id = ? AND id = ?
+            RETURNING {}",
+            LAYER_COLUMNS
+        );
+        sqlx::query_as::<_, SqliteLayerRow>(&query)
+            .bind(Uuid::new_v4())
+            .bind(new_name)
+            .bind(Json(source))
+            .bind(now)
+            .bind(now)
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to duplicate layer {}: {}", id, e))?
+            .ok_or_else(|| anyhow!("Layer {} not found", id))?
+            .try_into()
+    }
+
+    async fn expired(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<LayerSummary>> {
+        let query = format!(
+            "SELECT {} FROM layers WHERE expires_at <= ? ORDER BY expires_at",
+            LAYER_COLUMNS
+        );
+        let rows = sqlx::query_as::<_, SqliteLayerRow>(&query)
+            .bind(now)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to list expired layers: {}", e))?;
+
+        rows.into_iter()
+            .map(|row| Layer::try_from(row).map(|layer| layer.summary()))
+            .collect()
+    }
+
+    async fn update(&self, workspace_id: Uuid, id: Uuid, changes: &LayerUpdate) -> Result<Layer> {
+        update_layer(workspace_id, id, changes, &self.pool).await
+    }
+
+    async fn delete(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<DeletedLayer>> {
+        delete_layer(workspace_id, id, &self.pool).await
+    }
+
+    async fn delete_many(&self, workspace_id: Uuid, ids: &[Uuid]) -> Result<Vec<DeletedLayer>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let mut deleted = Vec::with_capacity(ids.len());
+        for id in ids {
+            deleted.extend(delete_layer(workspace_id, *id, &mut *tx).await?);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit layer deletion: {}", e))?;
+        Ok(deleted)
+    }
+
+    async fn update_many(
+        &self,
+        workspace_id: Uuid,
+        updates: &[(Uuid, LayerUpdate)],
+    ) -> Result<Vec<Layer>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let mut updated = Vec::with_capacity(updates.len());
+        for (id, changes) in updates {
+            updated.push(update_layer(workspace_id, *id, changes, &mut *tx).await?);
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit layer updates: {}", e))?;
+        Ok(updated)
+    }
+
+    async fn create_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        lineage: Option<&LayerLineage>,
+    ) -> Result<LayerVersion> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let (source,): (Option<Json<LayerSource>>,) =
+            sqlx::query_as("SELECT source FROM layers WHERE workspace_id = ? AND id = ?")
+                .bind(workspace_id)
+                .bind(layer_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| anyhow!("Failed to fetch layer {}: {}", layer_id, e))?
+                .ok_or_else(|| anyhow!("Layer {} not found or has no source table", layer_id))?;
+        let Some(Json(LayerSource::Database { name, .. })) = source else {
+            return Err(anyhow!(
+                "Layer {} not found or has no source table",
+                layer_id
+            ));
+        };
+
+        let (version,): (i64,) = sqlx::query_as(
+            "SELECT COALESCE(max(version), 0) + 1 FROM layer_versions WHERE layer_id = ?",
+        )
+        .bind(layer_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to number version of layer {}: {}", layer_id, e))?;
+
+        let row = sqlx::query_as::<_, VersionRow>(
+            "INSERT INTO layer_versions (layer_id, version, table_name, lineage, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING layer_id, version, table_name, lineage, created_at",
+        )
+        .bind(layer_id)
+        .bind(version)
+        .bind(LayerVersion::table_name_for(&name, version as u32))
+        .bind(lineage.map(Json))
+        .bind(chrono::Utc::now())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to create version of layer {}: {}", layer_id, e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit version of layer {}: {}", layer_id, e))?;
+        Ok(version_from_row(row))
+    }
+
+    async fn list_versions(&self, workspace_id: Uuid, layer_id: Uuid) -> Result<Vec<LayerVersion>> {
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM layer_versions v
+            JOIN layers l ON l.id = v.layer_id
+            WHERE v.layer_id = ? AND l.workspace_id = ?
+            ORDER BY v.version DESC",
+        )
+        .bind(layer_id)
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to list versions of layer {}: {}", layer_id, e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+
+    async fn get_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        version: Option<u32>,
+    ) -> Result<LayerVersion> {
+        sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM layer_versions v
+            JOIN layers l ON l.id = v.layer_id
+            WHERE v.layer_id = ? AND l.workspace_id = ? AND (?3 IS NULL OR v.version = ?3)
+            ORDER BY v.version DESC
+            LIMIT 1",
+        )
+        .bind(layer_id)
+        .bind(workspace_id)
+        .bind(version.map(|version| version as i64))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch version of layer {}: {}", layer_id, e))?
+        .map(version_from_row)
+        .ok_or_else(|| match version {
+            Some(version) => anyhow!("Version {} of layer {} not found", version, layer_id),
+            None => anyhow!("Layer {} has no versions", layer_id),
+        })
+    }
+
+    async fn find_versions_by_checksum(
+        &self,
+        workspace_id: Uuid,
+        sha256: &str,
+    ) -> Result<Vec<LayerVersion>> {
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "SELECT v.layer_id, v.version, v.table_name, v.lineage, v.created_at
+            FROM layer_versions v
+            JOIN layers l ON l.id = v.layer_id
+            WHERE l.workspace_id = ? AND json_extract(v.lineage, '$.sha256') = ?
+            ORDER BY v.created_at DESC",
+        )
+        .bind(workspace_id)
+        .bind(sha256)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to find versions by checksum: {}", e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+
+    async fn prune_versions(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        keep: u32,
+    ) -> Result<Vec<LayerVersion>> {
+        if keep == 0 {
+            return Err(anyhow!("At least one version must be kept"));
+        }
+        let rows = sqlx::query_as::<_, VersionRow>(
+            "DELETE FROM layer_versions
+            WHERE layer_id = ?1
+            AND EXISTS (SELECT 1 FROM layers WHERE id = ?1 AND workspace_id = ?3)
+            AND version NOT IN (
+                SELECT version FROM layer_versions
+                WHERE layer_id = ?1
+                ORDER BY version DESC
+                LIMIT ?2
+            )
+            RETURNING layer_id, version, table_name, lineage, created_at",
+        )
+        .bind(layer_id)
+        .bind(keep as i64)
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to prune versions of layer {}: {}", layer_id, e))?;
+        Ok(rows.into_iter().map(version_from_row).collect())
+    }
+
+    async fn queue_chunk_jobs(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        chunks: &[FeatureChunk],
+    ) -> Result<Vec<ChunkJob>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+        let now = chrono::Utc::now();
+        for chunk in chunks {
+            sqlx::query(
+                "INSERT INTO jobs (
+                    id, workspace_id, layer_id, kind, status, payload, created_at, updated_at
+                ) VALUES (?, ?, ?, ?, 'pending', ?, ?, ?)
+                ON CONFLICT DO NOTHING",
+            )
+            .bind(Uuid::new_v4())
+            .bind(workspace_id)
+            .bind(layer_id)
+            .bind(INGEST_CHUNK_JOB)
+            .bind(Json(chunk))
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to queue chunk {}: {}", chunk.index, e))?;
+        }
+
+        let pending: Vec<(Uuid, Json<FeatureChunk>)> = sqlx::query_as(
+            "SELECT j.id, j.payload
+            FROM jobs j
+            JOIN layers l ON l.id = j.layer_id
+            WHERE j.layer_id = ? AND l.workspace_id = ? AND j.kind = ?
+            AND j.status <> 'completed'
+            ORDER BY json_extract(j.payload, '$.index')",
+        )
+        .bind(layer_id)
+        .bind(workspace_id)
+        .bind(INGEST_CHUNK_JOB)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to read chunk jobs of layer {}: {}", layer_id, e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| anyhow!("Failed to commit chunk jobs: {}", e))?;
+
+        Ok(pending
+            .into_iter()
+            .map(|(id, chunk)| ChunkJob {
+                id,
+                layer_id,
+                chunk: chunk.0,
+            })
+            .collect())
+    }
+
+    async fn claim_chunk_job(&self, job_id: Uuid, lease: Duration) -> Result<bool> {
+        // SQLite runs one write at a time, so the conditional update is the claim
+        let now = chrono::Utc::now();
+        let expired = now
+            - chrono::Duration::from_std(lease).map_err(|e| anyhow!("Invalid job lease: {}", e))?;
+        let claimed = sqlx::query(
+            "UPDATE jobs
+            SET status = 'running', attempts = attempts + 1, updated_at = ?
+            WHERE id = ?
+            AND (status IN ('pending', 'failed') OR (status = 'running' AND updated_at < ?))",
+        )
+        .bind(now)
+        .bind(job_id)
+        .bind(expired)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to claim job {}: {}", job_id, e))?;
+        Ok(claimed.rows_affected() > 0)
+    }
+
+    async fn complete_chunk_job(&self, job_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE jobs SET status = 'completed', error = NULL, updated_at = ? WHERE id = ?",
+        )
+        .bind(chrono::Utc::now())
+        .bind(job_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| anyhow!("Failed to complete job {}: {}", job_id, e))?;
+        Ok(())
+    }
+
+    async fn fail_chunk_job(&self, job_id: Uuid, error: &str) -> Result<()> {
+        sqlx::query("UPDATE jobs SET status = 'failed', error = ?, updated_at = ? WHERE id = ?")
+            .bind(error)
+            .bind(chrono::Utc::now())
+            .bind(job_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| anyhow!("Failed to record failure of job {}: {}", job_id, e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::conversion::FeatureChunk;
+use crate::file::LayerSchema;
+use crate::{
+    ChunkJob, DeletedLayer, Layer, LayerLineage, LayerQuery, LayerSource, LayerSummary,
+    LayerUpdate, LayerVersion, Page,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Storage backend for [`Layer`] metadata.
+///
+/// [`LayerCore`](crate::LayerCore) is tied to Postgres executors so hosts can compose queries
+/// inside their own transactions; this trait is object-safe instead, so the backend can be
+/// chosen at runtime and gridwalk-core can run embedded on SQLite. Lookups are scoped to a
+/// workspace exactly as in `LayerCore`. The orchestration helpers such as
+/// [`delete_layer`](crate::delete_layer) work against any store.
+#[async_trait]
+pub trait MetadataStore: Send + Sync {
+    /// Create or prepare the metadata tables; safe to call on every start
+    async fn initialize(&self) -> Result<()>;
+
+    async fn save(&self, layer: &Layer) -> Result<()>;
+
+    /// One page of layers matching `query`
+    async fn list(
+        &self,
+        workspace_id: Uuid,
+        query: &LayerQuery,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Page<LayerSummary>>;
+
+    async fn get(&self, workspace_id: Uuid, id: Uuid) -> Result<Layer>;
+
+    async fn exists(&self, workspace_id: Uuid, id: Uuid) -> Result<bool>;
+
+    /// Schema stored at ingest time, `None` if no schema has been stored
+    async fn schema(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<LayerSchema>>;
+
+    /// PNG preview stored with the layer, `None` if no thumbnail has been rendered
+    async fn thumbnail(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<Vec<u8>>>;
+
+    /// Copy a layer's metadata to a new layer named `new_name` backed by `source`
+    async fn duplicate(
+        &self,
+        workspace_id: Uuid,
+        id: Uuid,
+        new_name: &str,
+        source: &LayerSource,
+    ) -> Result<Layer>;
+
+    /// Layers in any workspace whose `expires_at` is at or before `now`, oldest expiry first
+    async fn expired(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<LayerSummary>>;
+
+    /// Apply `changes` and return the updated layer, with the same transition semantics as
+    /// [`LayerCore::update`](crate::LayerCore::update)
+    async fn update(&self, workspace_id: Uuid, id: Uuid, changes: &LayerUpdate) -> Result<Layer>;
+
+    /// Delete the layer's metadata; returns `None` if it did not exist
+    async fn delete(&self, workspace_id: Uuid, id: Uuid) -> Result<Option<DeletedLayer>>;
+
+    /// Delete the metadata of every layer in `ids` in one transaction, returning those that
+    /// existed
+    async fn delete_many(&self, workspace_id: Uuid, ids: &[Uuid]) -> Result<Vec<DeletedLayer>>;
+
+    /// Apply every update in one transaction, so all are stored or none is
+    async fn update_many(
+        &self,
+        workspace_id: Uuid,
+        updates: &[(Uuid, LayerUpdate)],
+    ) -> Result<Vec<Layer>>;
+
+    /// Record a new version, as [`LayerVersionStore::create_version`](crate::LayerVersionStore)
+    async fn create_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        lineage: Option<&LayerLineage>,
+    ) -> Result<LayerVersion>;
+
+    /// Every version of a layer, newest first
+    async fn list_versions(&self, workspace_id: Uuid, layer_id: Uuid) -> Result<Vec<LayerVersion>>;
+
+    /// A specific version, or the latest when `version` is `None`
+    async fn get_version(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        version: Option<u32>,
+    ) -> Result<LayerVersion>;
+
+    /// Versions in a workspace ingested from a file with this SHA-256 checksum, newest first
+    async fn find_versions_by_checksum(
+        &self,
+        workspace_id: Uuid,
+        sha256: &str,
+    ) -> Result<Vec<LayerVersion>>;
+
+    /// Remove all but the newest `keep` version records, returning the removed versions. Fails
+    /// when `keep` is 0, so the newest version is never removed.
+    async fn prune_versions(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        keep: u32,
+    ) -> Result<Vec<LayerVersion>>;
+
+    /// Queue a pending job for each of `chunks` the layer has no job for yet, and return the
+    /// layer's chunk jobs that are not completed, in chunk order
+    async fn queue_chunk_jobs(
+        &self,
+        workspace_id: Uuid,
+        layer_id: Uuid,
+        chunks: &[FeatureChunk],
+    ) -> Result<Vec<ChunkJob>>;
+
+    /// Atomically mark a pending or failed chunk job `running`, or a `running` one whose claim
+    /// is older than `lease`. Returns `false` when the job is completed or held by a worker.
+    async fn claim_chunk_job(&self, job_id: Uuid, lease: Duration) -> Result<bool>;
+
+    /// Mark a claimed chunk job completed inside `rows`, the open Postgres transaction that
+    /// inserts the chunk's rows, so both commit together. Returns `Some(false)` when another
+    /// worker completed the job first, and `None` when the jobs are stored elsewhere and cannot
+    /// join the transaction; [`complete_chunk_job`](Self::complete_chunk_job) is then called
+    /// once the rows are committed.
+    async fn complete_chunk_job_with(
+        &self,
+        _job_id: Uuid,
+        _rows: &mut sqlx::PgConnection,
+    ) -> Result<Option<bool>> {
+        Ok(None)
+    }
+
+    /// Mark a claimed chunk job completed
+    async fn complete_chunk_job(&self, job_id: Uuid) -> Result<()>;
+
+    /// Record the error of a claimed chunk job so it can be claimed again
+    async fn fail_chunk_job(&self, job_id: Uuid, error: &str) -> Result<()>;
+}
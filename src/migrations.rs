@@ -0,0 +1,238 @@
+use anyhow::{Result, anyhow};
+use sqlx::PgPool;
+use tracing::debug;
+
+/// Schema holding the gridwalk metadata tables
+pub const METADATA_SCHEMA: &str = "gridwalk";
+
+/// Advisory lock key held while migrating, so concurrent instances initialise one at a time
+const MIGRATION_LOCK_ID: i64 = 0x6772_6964_7761_6c6b;
+
+/// A versioned schema change, applied once in version order
+struct Migration {
+    version: i64,
+    description: &'static str,
+    sql: &'static str,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create layers",
+        sql: "
+            CREATE TABLE gridwalk.layers (
+                id UUID PRIMARY KEY,
+                workspace_id UUID NOT NULL,
+                owner_id UUID,
+                name TEXT NOT NULL,
+                description TEXT,
+                status TEXT NOT NULL,
+                status_history JSONB NOT NULL DEFAULT '[]',
+                progress REAL,
+                status_message TEXT,
+                tags TEXT[] NOT NULL DEFAULT '{}',
+                metadata JSONB NOT NULL DEFAULT '{}',
+                stats JSONB,
+                schema JSONB,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            );
+            CREATE INDEX layers_workspace_created_idx
+                ON gridwalk.layers (workspace_id, created_at);
+            CREATE INDEX layers_tags_idx ON gridwalk.layers USING GIN (tags);
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "create layer versions",
+        sql: "
+            CREATE TABLE gridwalk.layer_versions (
+                layer_id UUID NOT NULL REFERENCES gridwalk.layers (id) ON DELETE CASCADE,
+                version INTEGER NOT NULL,
+                table_name TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (layer_id, version)
+            );
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "create layer audit log",
+        sql: "
+            CREATE TABLE gridwalk.layer_audit (
+                id BIGSERIAL PRIMARY KEY,
+                workspace_id UUID NOT NULL,
+                layer_id UUID NOT NULL,
+                actor_id UUID,
+                action TEXT NOT NULL,
+                details JSONB NOT NULL DEFAULT '{}',
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            );
+            CREATE INDEX layer_audit_layer_idx ON gridwalk.layer_audit (layer_id, created_at);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "create jobs",
+        sql: "
+            CREATE TABLE gridwalk.jobs (
+                id UUID PRIMARY KEY,
+                workspace_id UUID NOT NULL,
+                layer_id UUID REFERENCES gridwalk.layers (id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                status TEXT NOT NULL,
+                payload JSONB NOT NULL DEFAULT '{}',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                error TEXT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            );
+            CREATE INDEX jobs_status_idx ON gridwalk.jobs (status, created_at);
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "add layer connector id",
+        sql: "ALTER TABLE gridwalk.layers ADD COLUMN connector_id TEXT;",
+    },
+    Migration {
+        version: 6,
+        description: "add layer thumbnails",
+        sql: "ALTER TABLE gridwalk.layers ADD COLUMN thumbnail BYTEA;",
+    },
+    Migration {
+        version: 7,
+        description: "add layer version lineage",
+        sql: "ALTER TABLE gridwalk.layer_versions ADD COLUMN lineage JSONB;",
+    },
+    Migration {
+        version: 8,
+        description: "add layer source and expiry",
+        sql: "
+            ALTER TABLE gridwalk.layers
+                ADD COLUMN source JSONB,
+                ADD COLUMN expires_at TIMESTAMPTZ;
+            CREATE INDEX layers_expires_at_idx ON gridwalk.layers (expires_at)
+                WHERE expires_at IS NOT NULL;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "index layer version checksums",
+        sql: "
+            CREATE INDEX layer_versions_sha256_idx
+                ON gridwalk.layer_versions ((lineage->>'sha256'))
+                WHERE lineage IS NOT NULL;
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "make chunk jobs unique per layer",
+        sql: "
+            DELETE FROM gridwalk.jobs j
+            USING gridwalk.jobs other
+            WHERE other.layer_id = j.layer_id
+                AND other.kind = j.kind
+                AND other.payload->>'index' = j.payload->>'index'
+                AND (
+                    (other.status = 'completed') > (j.status = 'completed')
+                    OR (
+                        (other.status = 'completed') = (j.status = 'completed')
+                        AND (other.created_at, other.id) < (j.created_at, j.id)
+                    )
+                );
+            CREATE UNIQUE INDEX jobs_layer_kind_index_idx
+                ON gridwalk.jobs (layer_id, kind, (payload->>'index'));
+        ",
+    },
+];
+
+/// Create or upgrade the gridwalk metadata tables. Safe to call on every start-up: applied
+/// migrations are recorded in `gridwalk.schema_migrations` and skipped, and all pending ones
+/// run in a single transaction.
+pub async fn initialize(pool: &PgPool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start migration transaction: {}", e))?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_ID)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to acquire migration lock: {}", e))?;
+
+    sqlx::raw_sql(
+        "CREATE SCHEMA IF NOT EXISTS gridwalk;
+        CREATE TABLE IF NOT EXISTS gridwalk.schema_migrations (
+            version BIGINT PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Failed to create migrations table: {}", e))?;
+
+    let applied: Vec<i64> =
+        sqlx::query_as::<_, (i64,)>("SELECT version FROM gridwalk.schema_migrations")
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to read applied migrations: {}", e))?
+            .into_iter()
+            .map(|(version,)| version)
+            .collect();
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        debug!(
+            "Applying migration {}: {}",
+            migration.version, migration.description
+        );
+        sqlx::raw_sql(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Migration {} ({}) failed: {}",
+                    migration.version,
+                    migration.description,
+                    e
+                )
+            })?;
+        sqlx::query(
+            "INSERT INTO gridwalk.schema_migrations (version, description) VALUES ($1, $2)",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to record migration {}: {}", migration.version, e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit migrations: {}", e))?;
+    Ok(())
+}
+
+/// Latest applied migration version, `None` before [`initialize`] has run
+pub async fn schema_version(pool: &PgPool) -> Result<Option<i64>> {
+    let exists: (bool,) =
+        sqlx::query_as("SELECT to_regclass('gridwalk.schema_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("Failed to check migrations table: {}", e))?;
+    if !exists.0 {
+        return Ok(None);
+    }
+
+    let (version,): (Option<i64>,) =
+        sqlx::query_as("SELECT max(version) FROM gridwalk.schema_migrations")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("Failed to read schema version: {}", e))?;
+    Ok(version)
+}